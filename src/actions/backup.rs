@@ -0,0 +1,107 @@
+//! Export/import of all counters, timers and stopwatches, driven from the
+//! Counter property inspector (`sendToPlugin`). The result goes back to the
+//! PI as a `backupResult` message so it can show what happened.
+//!
+//! PI → plugin:
+//! `{ "event": "exportBackup" | "importBackup", "path": "...", "mode": "merge" | "replace" }`
+//! An empty path means `backup.json` in the plugin's data directory.
+
+use std::path::PathBuf;
+
+use chrono::{Local, SecondsFormat};
+use serde_json::{Value, json};
+use streamdeck_lib::prelude::*;
+use tracing::{info, warn};
+
+use crate::paths::data_dir;
+use crate::state::backup::{Backup, ImportMode};
+use crate::state::{export_backup, import_backup};
+use crate::topics::{
+    COUNTER_CHANGED, ChangeKind, CounterChanged, STOPWATCH_CTL, StopwatchControl, TIMER_CTL,
+    TimerControl,
+};
+
+/// Handle a PI message if it's a backup request; anything else is ignored.
+pub fn handle_pi_message(cx: &Context, ctx_id: &str, payload: &Value) {
+    let event = payload.get("event").and_then(|v| v.as_str());
+    let result = match event {
+        Some("exportBackup") => export(cx, payload),
+        Some("importBackup") => import(cx, ctx_id, payload),
+        _ => return,
+    };
+    let (ok, message) = match result {
+        Ok(msg) => {
+            info!("backup: {msg}");
+            (true, msg)
+        }
+        Err(msg) => {
+            warn!("backup: {msg}");
+            (false, msg)
+        }
+    };
+    cx.sd().send_to_property_inspector(
+        ctx_id,
+        json!({ "event": "backupResult", "ok": ok, "message": message }),
+    );
+}
+
+fn export(cx: &Context, payload: &Value) -> Result<String, String> {
+    let path = backup_path(payload)?;
+    let backup = export_backup(cx, Local::now().to_rfc3339_opts(SecondsFormat::Secs, false));
+    backup
+        .write_file(&path)
+        .map_err(|e| format!("could not write {}: {e}", path.display()))?;
+    Ok(format!(
+        "exported {} counters, {} timers, {} stopwatches to {}",
+        backup.counters.len(),
+        backup.timers.len(),
+        backup.stopwatches.len(),
+        path.display()
+    ))
+}
+
+fn import(cx: &Context, ctx_id: &str, payload: &Value) -> Result<String, String> {
+    let path = backup_path(payload)?;
+    let text = std::fs::read_to_string(&path)
+        .map_err(|e| format!("could not read {}: {e}", path.display()))?;
+    let backup = Backup::parse(&text)?;
+    let mode = match payload.get("mode").and_then(|v| v.as_str()) {
+        Some("replace") => ImportMode::Replace,
+        _ => ImportMode::Merge,
+    };
+
+    let imported = import_backup(cx, &backup, mode);
+    for c in &imported {
+        cx.bus().publish_t(
+            COUNTER_CHANGED,
+            CounterChanged {
+                counter_key: c.key.clone(),
+                previous: c.previous,
+                value: c.value,
+                op: "import".to_string(),
+                kind: ChangeKind::Import,
+                source: ctx_id.to_string(),
+            },
+        );
+    }
+    cx.bus().publish_t(TIMER_CTL, TimerControl::Reload);
+    cx.bus().publish_t(STOPWATCH_CTL, StopwatchControl::Reload);
+
+    Ok(format!(
+        "imported {} counters ({} changed), {} timers, {} stopwatches from {}",
+        backup.counters.len(),
+        imported.iter().filter(|c| c.changed()).count(),
+        backup.timers.len(),
+        backup.stopwatches.len(),
+        path.display()
+    ))
+}
+
+fn backup_path(payload: &Value) -> Result<PathBuf, String> {
+    match payload.get("path").and_then(|v| v.as_str()).map(str::trim) {
+        Some(p) if !p.is_empty() => Ok(PathBuf::from(p)),
+        _ => data_dir()
+            .map(|d| d.join("backup.json"))
+            .ok_or_else(|| "no data directory; enter a file path".to_string()),
+    }
+}
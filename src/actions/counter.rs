@@ -8,9 +8,23 @@ use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
 use streamdeck_lib::prelude::*;
 
-use crate::render::{Feedback, render_number, render_number_feedback};
-use crate::state::{counter_key, init_or_load_counter, read_counter, write_counter};
-use crate::topics::{COUNTER_CHANGED, CounterChanged};
+use crate::actions::{backup, storage};
+use crate::render::{
+    CountStyle, Feedback, GoalProgress, LABEL_MAX_LINES, MAX_MARKS, NumberFormat, render_counter,
+    render_dial, render_marks,
+};
+use crate::state::bounds::{BoundPolicy, Bounds};
+use crate::state::changelog::LogFormat;
+use crate::state::goal::{Goal, GoalStyle};
+use crate::state::group::GroupMember;
+use crate::state::schedule::{Schedule, ScheduleRecord, parse_time_of_day};
+use crate::state::triggers::{Trigger, background, parse_triggers};
+use crate::state::{
+    apply_to_counters, counter_key, forget_key, init_or_load_counter, read_bounds, read_counter,
+    read_goal, read_initial, read_triggers, write_bounds, write_goal, write_group, write_initial,
+    write_log_format, write_schedule, write_triggers,
+};
+use crate::topics::{COUNTER_CHANGED, COUNTER_TRIGGERED, ChangeKind, CounterChanged};
 
 pub struct CounterAction {
     // Long-press epoch tracking (same atomic pattern as the old plugin)
@@ -20,9 +34,24 @@ pub struct CounterAction {
     active_press_id: Arc<AtomicU64>,
     long_fired_press_id: Arc<AtomicU64>,
 
+    // Dial acceleration: when the last rotation arrived, and how many quick
+    // rotations in a row preceded it.
+    last_turn: Option<Instant>,
+    turn_streak: u32,
+    /// Whether this context sits on a Stream Deck+ dial, from willAppear.
+    encoder: bool,
+
     // Cached resolved state for key_up to reference without re-parsing
     counter_key: Option<String>,
     active: Option<CounterSettings>,
+    /// Latest settings, cached for re-renders and flashes driven by bus events.
+    settings: CounterSettings,
+    /// True once this key has written bounds to the store, so clearing them in
+    /// the PI removes the shared entry (but an unbounded sibling key doesn't).
+    published_bounds: bool,
+    /// Same as `published_bounds`, for the shared initial value and goal.
+    published_initial: bool,
+    published_goal: bool,
 }
 
 impl Default for CounterAction {
@@ -33,8 +62,15 @@ impl Default for CounterAction {
             press_seq: 0,
             active_press_id: Arc::new(AtomicU64::new(0)),
             long_fired_press_id: Arc::new(AtomicU64::new(0)),
+            last_turn: None,
+            turn_streak: 0,
+            encoder: false,
             counter_key: None,
             active: None,
+            settings: CounterSettings::default(),
+            published_bounds: false,
+            published_initial: false,
+            published_goal: false,
         }
     }
 }
@@ -49,28 +85,67 @@ impl Action for CounterAction {
     }
 
     fn topics(&self) -> &'static [&'static str] {
-        &[COUNTER_CHANGED.name]
+        &[COUNTER_CHANGED.name, COUNTER_TRIGGERED.name]
     }
 
     fn init(&mut self, cx: &Context, ctx_id: &str) {
         cx.sd().get_settings(ctx_id);
     }
 
+    fn teardown(&mut self, cx: &Context, ctx_id: &str) {
+        forget_key(cx, ctx_id);
+    }
+
+    fn will_appear(&mut self, _cx: &Context, ev: &incoming::WillAppear) {
+        self.encoder = ev.controller == "Encoder";
+    }
+
     fn did_receive_settings(&mut self, cx: &Context, ev: &incoming::DidReceiveSettings) {
         let settings = parse_settings(&ev.settings);
         let key = counter_key(&settings.counter_id, ev.context);
         self.counter_key = Some(key.clone());
+        let bounds = settings.bounds();
+        if !bounds.is_unbounded() || self.published_bounds {
+            write_bounds(cx, &key, &bounds);
+            self.published_bounds = !bounds.is_unbounded();
+        }
+        if settings.initial_value != 0 || self.published_initial {
+            write_initial(cx, &key, settings.initial_value);
+            self.published_initial = settings.initial_value != 0;
+        }
+        let goal = settings.goal();
+        if goal.is_some() || self.published_goal {
+            write_goal(cx, &key, goal.as_ref());
+            self.published_goal = goal.is_some();
+        }
+        // These are this key's own, so they follow its settings exactly.
+        let ctx_id = ev.context;
+        write_triggers(cx, ctx_id, &key, &settings.triggers);
+        write_log_format(cx, ctx_id, &key, settings.change_log);
+        write_group(cx, ctx_id, &key, settings.group().as_ref());
+        write_schedule(cx, ctx_id, &key, settings.schedule().as_ref());
         let current = init_or_load_counter(cx, &key, settings.initial_value);
-        render_number(cx, ev.context, current);
+        show(cx, ev.context, self.encoder, &key, current, &settings, None);
+        self.settings = settings;
+    }
+
+    fn did_receive_property_inspector_message(
+        &mut self,
+        cx: &Context,
+        ev: &incoming::DidReceivePropertyInspectorMessage,
+    ) {
+        backup::handle_pi_message(cx, ev.context, &ev.payload);
+        storage::handle_pi_message(cx, ev.context, &ev.payload);
     }
 
     fn key_down(&mut self, cx: &Context, ev: &incoming::KeyDown) {
         let settings = parse_settings(&ev.settings);
         let key = counter_key(&settings.counter_id, ev.context);
         self.counter_key = Some(key.clone());
+        self.settings = settings.clone();
 
         let current = init_or_load_counter(cx, &key, settings.initial_value);
-        render_number(cx, ev.context, current);
+        show(cx, ev.context, self.encoder, &key, current, &settings, None);
 
         // Start a new press epoch
         self.active = Some(settings.clone());
@@ -88,6 +163,7 @@ impl Action for CounterAction {
         let fired_id = Arc::clone(&self.long_fired_press_id);
         let cx2 = cx.clone();
         let ctx = ev.context.to_string();
+        let encoder = self.encoder;
 
         std::thread::spawn(move || {
             std::thread::sleep(Duration::from_millis(settings.long_press_ms));
@@ -102,25 +178,26 @@ impl Action for CounterAction {
 
             fired_id.store(pid, Ordering::SeqCst);
 
-            let base = read_counter(&cx2, &key, settings.initial_value);
-            let next = apply(
-                settings.long_action,
-                base,
-                settings.long_value,
-                settings.initial_value,
-            );
-            if next != base || matches!(settings.long_action, Op::Reset) {
-                write_counter(&cx2, &key, next);
-                cx2.bus().publish_t(
-                    COUNTER_CHANGED,
-                    CounterChanged { counter_key: key.clone(), value: next },
-                );
+            if matches!(settings.hold_mode, HoldMode::Repeat) {
+                auto_repeat(&cx2, &ctx, &key, &settings, &holding, &active_id, pid);
+                return;
+            }
+
+            match apply_press(&cx2, &ctx, &key, &settings, Press::Long) {
                 // Confirm the long-press took effect with an edge-visible OK
                 // vignette (the finger covers the center), then revert.
-                flash_feedback(&cx2, &ctx, &key, settings.initial_value, Feedback::Ok);
-            } else if !matches!(settings.long_action, Op::None) {
+                Outcome::Applied => {
+                    flash_feedback(&cx2, &ctx, encoder, &key, &settings, Feedback::Ok)
+                }
+                // Rejected by a counter's bounds.
+                Outcome::Rejected => {
+                    flash_feedback(&cx2, &ctx, encoder, &key, &settings, Feedback::Alert)
+                }
                 // A long action was configured but had no effect (e.g. ÷0, ×1).
-                flash_feedback(&cx2, &ctx, &key, settings.initial_value, Feedback::Alert);
+                Outcome::Unchanged if settings.has_long_action() => {
+                    flash_feedback(&cx2, &ctx, encoder, &key, &settings, Feedback::Alert);
+                }
+                Outcome::Unchanged => {}
             }
         });
     }
@@ -137,26 +214,33 @@ impl Action for CounterAction {
         let Some(settings) = self.active.clone() else {
             return;
         };
+        self.short_press(cx, ev.context, settings);
+    }
 
-        let key = counter_key(&settings.counter_id, ev.context);
-        let base = read_counter(cx, &key, settings.initial_value);
-        let next = apply(
-            settings.short_action,
-            base,
-            settings.short_value,
-            settings.initial_value,
-        );
+    fn dial_rotate(&mut self, cx: &Context, ev: &incoming::DialRotate) {
+        let now = Instant::now();
+        let quick = self
+            .last_turn
+            .is_some_and(|t| now.duration_since(t) < Duration::from_millis(DIAL_QUICK_MS));
+        self.turn_streak = if quick { self.turn_streak + 1 } else { 0 };
+        self.last_turn = Some(now);
 
-        if next == base && !matches!(settings.short_action, Op::Reset) {
-            return;
-        }
+        let step = self.settings_for_dial(ev.context, &ev.settings).dial_step;
+        let n = i64::from(ev.ticks)
+            .saturating_mul(step)
+            .saturating_mul(dial_multiplier(self.turn_streak));
+        self.dial_op(cx, ev.context, Op::Add, n);
+    }
 
-        write_counter(cx, &key, next);
-        cx.bus().publish_t(
-            COUNTER_CHANGED,
-            CounterChanged { counter_key: key.clone(), value: next },
-        );
-        render_number(cx, ev.context, next);
+    fn dial_down(&mut self, cx: &Context, ev: &incoming::DialDown) {
+        self.settings_for_dial(ev.context, &ev.settings);
+        self.dial_op(cx, ev.context, Op::Reset, 0);
+    }
+
+    fn touch_tap(&mut self, cx: &Context, ev: &incoming::TouchTap) {
+        let s = self.settings_for_dial(ev.context, &ev.settings);
+        let value = s.touch_value.unwrap_or(s.initial_value);
+        self.dial_op(cx, ev.context, Op::Set, value);
     }
 
     fn on_notify(&mut self, cx: &Context, ctx_id: &str, event: &ErasedTopic) {
@@ -166,15 +250,79 @@ impl Action for CounterAction {
                 None => ctx_id,
             };
             if n.counter_key == my_key {
-                render_number(cx, ctx_id, n.value);
+                let s = &self.settings;
+                show(cx, ctx_id, self.encoder, my_key, n.value, s, None);
+            }
+        } else if let Some(t) = event.downcast(COUNTER_TRIGGERED) {
+            if !t.event.is_empty() && t.event == self.settings.on_event {
+                self.short_press(cx, ctx_id, self.settings.clone());
+            }
+            let my_key = self.counter_key.as_deref().unwrap_or(ctx_id);
+            if t.counter_key != my_key {
+                return;
+            }
+            if t.flash {
+                // flash_feedback sleeps, so keep it off the event thread.
+                let (cx2, ctx, key) = (cx.clone(), ctx_id.to_string(), my_key.to_string());
+                let (settings, encoder) = (self.settings.clone(), self.encoder);
+                std::thread::spawn(move || {
+                    flash_feedback(&cx2, &ctx, encoder, &key, &settings, Feedback::Alert);
+                });
+            }
+        }
+    }
+}
+
+impl CounterAction {
+    /// Run the short-press action, from a key release or a trigger event.
+    fn short_press(&self, cx: &Context, ctx_id: &str, settings: CounterSettings) {
+        let key = counter_key(&settings.counter_id, ctx_id);
+        match apply_press(cx, ctx_id, &key, &settings, Press::Short) {
+            Outcome::Applied => {
+                let value = read_counter(cx, &key, settings.initial_value);
+                show(cx, ctx_id, self.encoder, &key, value, &settings, None);
+            }
+            Outcome::Rejected => {
+                // Rejected by a counter's bounds — flash off the event thread.
+                let (cx2, ctx, encoder) = (cx.clone(), ctx_id.to_string(), self.encoder);
+                std::thread::spawn(move || {
+                    flash_feedback(&cx2, &ctx, encoder, &key, &settings, Feedback::Alert);
+                });
             }
+            Outcome::Unchanged => {}
+        }
+    }
+
+    /// Refresh the cached settings and key from a dial event.
+    fn settings_for_dial(&mut self, ctx_id: &str, v: &Map<String, Value>) -> &CounterSettings {
+        let settings = parse_settings(v);
+        self.counter_key = Some(counter_key(&settings.counter_id, ctx_id));
+        self.settings = settings;
+        &self.settings
+    }
+
+    /// Apply one op from the dial or touch strip to this key's counter. There
+    /// is no finger over the strip to hide it, so a rejection uses the native
+    /// alert rather than the edge vignette.
+    fn dial_op(&self, cx: &Context, ctx_id: &str, op: Op, n: i64) {
+        let Some(key) = self.counter_key.clone() else {
+            return;
+        };
+        let init = self.settings.initial_value;
+        match apply_ops(cx, ctx_id, [(key.clone(), op, n, init)]) {
+            Outcome::Applied => {
+                let value = read_counter(cx, &key, init);
+                show(cx, ctx_id, self.encoder, &key, value, &self.settings, None);
+            }
+            Outcome::Rejected => cx.sd().show_alert(ctx_id),
+            Outcome::Unchanged => {}
         }
     }
 }
 
 // ── Settings ────────────────────────────────────────────────────────────────
 
-#[derive(Clone, Copy, Debug, Serialize, Deserialize, Default)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize, Default)]
 #[serde(rename_all = "lowercase")]
 pub enum Op {
     #[default]
@@ -185,6 +333,72 @@ pub enum Op {
     Divide,
     Reset,
     Set,
+    /// Remainder after dividing by the value (always in `0..value`).
+    Modulo,
+    /// Raise to the value's power, saturating at the i64 limits.
+    Power,
+    /// Raise to the value if below it (`max(current, value)`).
+    AtLeast,
+    /// Lower to the value if above it (`min(current, value)`).
+    AtMost,
+    /// Roll a number from 1 to the value, like an N-sided die.
+    Random,
+    /// Switch between the value and the initial value.
+    Toggle,
+}
+
+impl Op {
+    /// Ops whose result counts as a change even when it equals the current
+    /// value: a reset is still a reset, and a die can roll the same number.
+    fn always_writes(self) -> bool {
+        matches!(self, Op::Reset | Op::Random)
+    }
+
+    /// Short description for the change log, e.g. `add 5` or `reset`.
+    pub fn describe(self, n: i64) -> String {
+        let name = match self {
+            Op::None => return "none".to_string(),
+            Op::Reset => return "reset".to_string(),
+            Op::Add => "add",
+            Op::Subtract => "subtract",
+            Op::Multiply => "multiply",
+            Op::Divide => "divide",
+            Op::Set => "set",
+            Op::Modulo => "modulo",
+            Op::Power => "power",
+            Op::AtLeast => "at least",
+            Op::AtMost => "at most",
+            Op::Random => "random",
+            Op::Toggle => "toggle",
+        };
+        format!("{name} {n}")
+    }
+
+    pub fn kind(self) -> ChangeKind {
+        match self {
+            Op::Add | Op::Subtract => ChangeKind::Step,
+            Op::Reset => ChangeKind::Reset,
+            _ => ChangeKind::Set,
+        }
+    }
+}
+
+/// Another counter a press acts on, alongside the key's own.
+#[derive(Clone, Debug, PartialEq)]
+struct Target {
+    counter_id: String,
+    op: Op,
+    value: i64,
+}
+
+/// What holding the key past `longPressMs` does.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+enum HoldMode {
+    /// Fire the long-press action once.
+    #[default]
+    Long,
+    /// Repeat the short action, accelerating, until the key is released.
+    Repeat,
 }
 
 #[derive(Clone, Debug)]
@@ -195,7 +409,92 @@ struct CounterSettings {
     short_value: i64,
     long_action: Op,
     long_value: i64,
+    /// Other counters each press also acts on, in order.
+    short_targets: Vec<Target>,
+    long_targets: Vec<Target>,
     long_press_ms: u64,
+    hold_mode: HoldMode,
+    /// Amount one dial tick adds (negative ticks subtract it).
+    dial_step: i64,
+    /// What a tap on the touch strip sets the counter to; the initial value
+    /// when unset.
+    touch_value: Option<i64>,
+    /// Auto-repeat rate (steps per second) when the repeat starts...
+    repeat_start_hz: f64,
+    /// ...and the rate it accelerates to over [`REPEAT_RAMP_MS`].
+    repeat_end_hz: f64,
+    min_value: Option<i64>,
+    max_value: Option<i64>,
+    bound_policy: BoundPolicy,
+    format: NumberFormat,
+    goal: Option<i64>,
+    goal_style: GoalStyle,
+    goal_beep: bool,
+    triggers: Vec<Trigger>,
+    /// Trigger event (a rule's `publish NAME`) that presses this key.
+    on_event: String,
+    change_log: Option<LogFormat>,
+    group: String,
+    reset_schedule: Option<Schedule>,
+    /// Cycle mode: the value is an index into these, shown instead of it.
+    labels: Vec<String>,
+    label_wrap: bool,
+    /// Draw small values as marks; [`CountStyle::draws`] decides when.
+    count_style: CountStyle,
+    /// Highest value drawn as marks, and the number of pips.
+    count_limit: i64,
+}
+
+impl CounterSettings {
+    /// The PI bounds — or, in cycle mode, the label list's index range, which
+    /// wraps or stops at the ends.
+    fn bounds(&self) -> Bounds {
+        if !self.labels.is_empty() {
+            let policy = if self.label_wrap {
+                BoundPolicy::Wrap
+            } else {
+                BoundPolicy::Clamp
+            };
+            return Bounds::new(Some(0), Some(self.labels.len() as i64 - 1), policy);
+        }
+        Bounds::new(self.min_value, self.max_value, self.bound_policy)
+    }
+
+    /// The cycle label for `value`, if the key has labels and the value is a
+    /// valid index (anything else falls back to the number).
+    fn label(&self, value: i64) -> Option<&str> {
+        let i = usize::try_from(value).ok()?;
+        self.labels.get(i).map(String::as_str)
+    }
+
+    fn group(&self) -> Option<GroupMember> {
+        let group = self.group.trim();
+        (!group.is_empty()).then(|| GroupMember {
+            group: group.to_string(),
+            initial: self.initial_value,
+        })
+    }
+
+    fn schedule(&self) -> Option<ScheduleRecord> {
+        self.reset_schedule.map(|schedule| ScheduleRecord {
+            schedule,
+            initial: self.initial_value,
+        })
+    }
+
+    /// Whether a long press is meant to do anything, so one that changed
+    /// nothing is flagged.
+    fn has_long_action(&self) -> bool {
+        !matches!(self.long_action, Op::None) || !self.long_targets.is_empty()
+    }
+
+    fn goal(&self) -> Option<Goal> {
+        self.goal.map(|target| Goal {
+            target,
+            style: self.goal_style,
+            beep: self.goal_beep,
+        })
+    }
 }
 
 impl Default for CounterSettings {
@@ -207,7 +506,30 @@ impl Default for CounterSettings {
             short_value: 1,
             long_action: Op::None,
             long_value: 0,
+            short_targets: Vec::new(),
+            long_targets: Vec::new(),
             long_press_ms: 500,
+            hold_mode: HoldMode::Long,
+            dial_step: 1,
+            touch_value: None,
+            repeat_start_hz: 4.0,
+            repeat_end_hz: 20.0,
+            min_value: None,
+            max_value: None,
+            bound_policy: BoundPolicy::Clamp,
+            format: NumberFormat::default(),
+            goal: None,
+            goal_style: GoalStyle::Bar,
+            goal_beep: false,
+            triggers: Vec::new(),
+            on_event: String::new(),
+            change_log: None,
+            group: String::new(),
+            reset_schedule: None,
+            labels: Vec::new(),
+            label_wrap: false,
+            count_style: CountStyle::Digits,
+            count_limit: 10,
         }
     }
 }
@@ -220,16 +542,116 @@ fn parse_settings(v: &Map<String, Value>) -> CounterSettings {
     s.short_value = get_i64(v, "shortValue").unwrap_or(1);
     s.long_action = get_op(v, "longAction").unwrap_or(Op::None);
     s.long_value = get_i64(v, "longValue").unwrap_or(0);
+    s.short_targets = parse_targets(get_str(v, "shortTargets").unwrap_or(""));
+    s.long_targets = parse_targets(get_str(v, "longTargets").unwrap_or(""));
     if let Some(ms) = get_u64(v, "longPressMs") {
         s.long_press_ms = ms;
     }
+    s.hold_mode = match get_str(v, "holdMode") {
+        Some("repeat") => HoldMode::Repeat,
+        _ => HoldMode::Long,
+    };
+    s.dial_step = get_i64(v, "dialStep").unwrap_or(1);
+    s.touch_value = get_i64(v, "touchValue");
+    if let Some(hz) = get_f64(v, "repeatStartRate") {
+        s.repeat_start_hz = hz.clamp(MIN_REPEAT_HZ, MAX_REPEAT_HZ);
+    }
+    if let Some(hz) = get_f64(v, "repeatEndRate") {
+        s.repeat_end_hz = hz.clamp(MIN_REPEAT_HZ, MAX_REPEAT_HZ);
+    }
+    s.min_value = get_i64(v, "minValue");
+    s.max_value = get_i64(v, "maxValue");
+    s.bound_policy = get_policy(v, "boundPolicy").unwrap_or_default();
+    s.format = NumberFormat::from_settings(v);
+    s.goal = get_i64(v, "goal");
+    s.goal_style = match get_str(v, "goalStyle") {
+        Some("ring") => GoalStyle::Ring,
+        _ => GoalStyle::Bar,
+    };
+    s.goal_beep = get_bool(v, "goalBeep");
+    s.triggers = parse_triggers(get_str(v, "triggers").unwrap_or(""));
+    s.on_event = get_str(v, "onEvent").unwrap_or("").trim().to_string();
+    s.change_log = match get_str(v, "changeLog") {
+        Some("jsonl") => Some(LogFormat::Jsonl),
+        Some("csv") => Some(LogFormat::Csv),
+        _ => None,
+    };
+    s.group = get_str(v, "group").unwrap_or("").to_string();
+    s.reset_schedule = parse_schedule(v);
+    s.labels = parse_labels(get_str(v, "labels").unwrap_or(""));
+    s.label_wrap = get_bool(v, "labelWrap");
+    s.count_style = match get_str(v, "countStyle") {
+        Some("tally") => CountStyle::Tally,
+        Some("pips") => CountStyle::Pips,
+        Some("dots") => CountStyle::Dots,
+        _ => CountStyle::Digits,
+    };
+    s.count_limit = get_i64(v, "countLimit").unwrap_or(10).clamp(1, MAX_MARKS);
     s
 }
 
+/// Split a cycle list on commas or line breaks, dropping blank entries.
+fn parse_labels(text: &str) -> Vec<String> {
+    text.split([',', '\n'])
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// One target per line: `<counter id> <op> [value]`, e.g. `attempts subtract 1`.
+/// The value defaults to 1; the ID may contain spaces. Unreadable lines are
+/// skipped.
+fn parse_targets(text: &str) -> Vec<Target> {
+    text.lines().filter_map(parse_target).collect()
+}
+
+fn parse_target(line: &str) -> Option<Target> {
+    let mut words: Vec<&str> = line.split_whitespace().collect();
+    let value = match words.last()?.parse() {
+        Ok(n) => {
+            words.pop();
+            n
+        }
+        Err(_) => 1,
+    };
+    let op = parse_op(words.pop()?)?;
+    (!words.is_empty()).then(|| Target {
+        counter_id: words.join(" "),
+        op,
+        value,
+    })
+}
+
+fn parse_schedule(v: &Map<String, Value>) -> Option<Schedule> {
+    let at = get_str(v, "resetTime")
+        .and_then(parse_time_of_day)
+        .unwrap_or(0);
+    match get_str(v, "resetEvery")? {
+        "day" => Some(Schedule::Day { at }),
+        "week" => Some(Schedule::Week {
+            weekday: get_u64(v, "resetWeekday").unwrap_or(0).min(6) as u32,
+            at,
+        }),
+        "month" => Some(Schedule::Month {
+            day: get_u64(v, "resetMonthDay").unwrap_or(1).clamp(1, 31) as u32,
+            at,
+        }),
+        "hours" => Some(Schedule::Hours {
+            hours: get_u64(v, "resetHours").unwrap_or(24).clamp(1, 24 * 366) as u32,
+        }),
+        _ => None,
+    }
+}
+
 fn get_str<'a>(v: &'a Map<String, Value>, k: &str) -> Option<&'a str> {
     v.get(k)?.as_str()
 }
 
+fn get_bool(v: &Map<String, Value>, k: &str) -> bool {
+    v.get(k).and_then(|b| b.as_bool()).unwrap_or(false)
+}
+
 fn get_i64(v: &Map<String, Value>, k: &str) -> Option<i64> {
     match v.get(k) {
         Some(Value::Number(n)) => n.as_i64(),
@@ -246,8 +668,21 @@ fn get_u64(v: &Map<String, Value>, k: &str) -> Option<u64> {
     }
 }
 
+fn get_f64(v: &Map<String, Value>, k: &str) -> Option<f64> {
+    match v.get(k) {
+        Some(Value::Number(n)) => n.as_f64(),
+        Some(Value::String(s)) => s.trim().parse().ok(),
+        _ => None,
+    }
+    .filter(|f: &f64| f.is_finite())
+}
+
 fn get_op(v: &Map<String, Value>, k: &str) -> Option<Op> {
-    get_str(v, k).and_then(|s| match s {
+    get_str(v, k).and_then(parse_op)
+}
+
+fn parse_op(s: &str) -> Option<Op> {
+    match s {
         "none" => Some(Op::None),
         "add" => Some(Op::Add),
         "subtract" => Some(Op::Subtract),
@@ -255,6 +690,21 @@ fn get_op(v: &Map<String, Value>, k: &str) -> Option<Op> {
         "divide" => Some(Op::Divide),
         "reset" => Some(Op::Reset),
         "set" => Some(Op::Set),
+        "modulo" => Some(Op::Modulo),
+        "power" => Some(Op::Power),
+        "atleast" => Some(Op::AtLeast),
+        "atmost" => Some(Op::AtMost),
+        "random" => Some(Op::Random),
+        "toggle" => Some(Op::Toggle),
+        _ => None,
+    }
+}
+
+fn get_policy(v: &Map<String, Value>, k: &str) -> Option<BoundPolicy> {
+    get_str(v, k).and_then(|s| match s {
+        "clamp" => Some(BoundPolicy::Clamp),
+        "wrap" => Some(BoundPolicy::Wrap),
+        "reject" => Some(BoundPolicy::Reject),
         _ => None,
     })
 }
@@ -264,17 +714,219 @@ fn get_op(v: &Map<String, Value>, k: &str) -> Option<Op> {
 /// Flash an edge-vignette [`Feedback`] over the live counter value, then revert
 /// to the current value after a short delay. Called from the long-press thread,
 /// which is already detached, so the blocking sleep is fine here.
-fn flash_feedback(cx: &Context, ctx_id: &str, key: &str, initial: i64, fb: Feedback) {
+fn flash_feedback(
+    cx: &Context,
+    ctx_id: &str,
+    encoder: bool,
+    key: &str,
+    s: &CounterSettings,
+    fb: Feedback,
+) {
     const FEEDBACK_MS: u64 = 650;
-    let value = read_counter(cx, key, initial);
-    render_number_feedback(cx, ctx_id, value, fb);
+    let value = read_counter(cx, key, s.initial_value);
+    show(cx, ctx_id, encoder, key, value, s, Some(fb));
     std::thread::sleep(Duration::from_millis(FEEDBACK_MS));
-    render_number(cx, ctx_id, read_counter(cx, key, initial));
+    let value = read_counter(cx, key, s.initial_value);
+    show(cx, ctx_id, encoder, key, value, s, None);
+}
+
+/// Render `value` on a counter key — as its cycle label when the key has a
+/// label list, or as marks when it's small enough for its count style — with
+/// progress when the counter has a shared goal and the
+/// background of any trigger rule it currently satisfies. `fb` overlays a
+/// [`Feedback`] vignette.
+///
+/// On a dial, the touch strip gets the same text under the counter ID, with a
+/// bar for the goal progress or, failing that, where the value sits between
+/// its bounds.
+fn show(
+    cx: &Context,
+    ctx_id: &str,
+    encoder: bool,
+    key: &str,
+    value: i64,
+    s: &CounterSettings,
+    fb: Option<Feedback>,
+) {
+    let goal = read_goal(cx, key).map(|g| GoalProgress {
+        fraction: g.progress(value),
+        style: g.style,
+        reached: g.reached(value),
+    });
+    let bg = background(&read_triggers(cx, key), value);
+    let formatted;
+    let (text, max_lines) = match s.label(value) {
+        Some(label) => (label, LABEL_MAX_LINES),
+        None => {
+            formatted = s.format.format(value);
+            (formatted.as_str(), 1)
+        }
+    };
+    if s.label(value).is_none() && s.count_style.draws(value, s.count_limit) {
+        render_marks(cx, ctx_id, s.count_style, value, s.count_limit, bg, fb);
+    } else {
+        render_counter(cx, ctx_id, text, max_lines, bg, goal, fb);
+    }
+
+    if encoder {
+        let bar = match goal {
+            Some(g) => Some(g.fraction),
+            None => read_bounds(cx, key).fraction(value),
+        };
+        render_dial(cx, ctx_id, s.counter_id.trim(), text, bar);
+    }
+}
+
+// ── Auto-repeat ─────────────────────────────────────────────────────────────
+
+/// Time for the repeat rate to ramp from its start rate to its end rate.
+const REPEAT_RAMP_MS: u64 = 2_000;
+/// Rate limits; the top end keeps us well clear of flooding `setImage`.
+const MIN_REPEAT_HZ: f64 = 0.5;
+const MAX_REPEAT_HZ: f64 = 30.0;
+
+/// Apply the short action repeatedly while the key stays held, accelerating
+/// per [`repeat_interval`]. Runs on the long-press thread. The press epoch is
+/// re-checked before every step, so a release — or a newer press — stops the
+/// loop before it can apply a stray step.
+fn auto_repeat(
+    cx: &Context,
+    ctx_id: &str,
+    key: &str,
+    s: &CounterSettings,
+    holding: &AtomicBool,
+    active_id: &AtomicU64,
+    pid: u64,
+) {
+    let started = Instant::now();
+    while holding.load(Ordering::SeqCst) && active_id.load(Ordering::SeqCst) == pid {
+        match apply_press(cx, ctx_id, key, s, Press::Short) {
+            Outcome::Rejected => {
+                // Only keys are held, so there's no touch strip here.
+                flash_feedback(cx, ctx_id, false, key, s, Feedback::Alert);
+                return;
+            }
+            // Pinned at a bound (or a no-op action): nothing left to repeat.
+            Outcome::Unchanged => return,
+            Outcome::Applied => {}
+        }
+        std::thread::sleep(repeat_interval(
+            started.elapsed(),
+            s.repeat_start_hz,
+            s.repeat_end_hz,
+        ));
+    }
+}
+
+/// Delay before the next repeat step, `held` into the repeat. The rate moves
+/// linearly from `start_hz` to `end_hz` over [`REPEAT_RAMP_MS`], then holds.
+fn repeat_interval(held: Duration, start_hz: f64, end_hz: f64) -> Duration {
+    let t = (held.as_millis() as f64 / REPEAT_RAMP_MS as f64).min(1.0);
+    let hz = (start_hz + (end_hz - start_hz) * t).clamp(MIN_REPEAT_HZ, MAX_REPEAT_HZ);
+    Duration::from_secs_f64(1.0 / hz)
+}
+
+// ── Dial ────────────────────────────────────────────────────────────────────
+
+/// Rotations closer together than this count as one quick turn.
+const DIAL_QUICK_MS: u64 = 120;
+
+/// Step multiplier after `streak` quick rotations in a row, so a fast spin
+/// covers large ranges while a slow turn still moves one step at a time.
+fn dial_multiplier(streak: u32) -> i64 {
+    match streak {
+        0..4 => 1,
+        4..8 => 2,
+        8..16 => 5,
+        _ => 10,
+    }
+}
+
+// ── Presses ─────────────────────────────────────────────────────────────────
+
+#[derive(Clone, Copy)]
+enum Press {
+    Short,
+    Long,
+}
+
+/// How a press's batch of changes went.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Outcome {
+    /// At least one counter changed.
+    Applied,
+    /// Every op left its counter as it was (e.g. ×1, or pinned at a bound).
+    Unchanged,
+    /// A counter's bounds rejected its result; nothing was written.
+    Rejected,
+}
+
+/// Apply a press — the key's own action, then its extra targets — as one
+/// batch. Every result is computed and written before any `COUNTER_CHANGED`
+/// goes out, so computed displays never see a half-applied press. It's all
+/// or nothing: if any counter's bounds reject its result, none are written.
+/// A counter named twice sees the earlier op's result.
+fn apply_press(
+    cx: &Context,
+    ctx_id: &str,
+    key: &str,
+    s: &CounterSettings,
+    press: Press,
+) -> Outcome {
+    let (op, n, targets) = match press {
+        Press::Short => (s.short_action, s.short_value, &s.short_targets),
+        Press::Long => (s.long_action, s.long_value, &s.long_targets),
+    };
+    let own = (key.to_string(), op, n, s.initial_value);
+    let others = targets.iter().map(|t| {
+        let init = read_initial(cx, &t.counter_id);
+        (t.counter_id.clone(), t.op, t.value, init)
+    });
+    apply_ops(cx, ctx_id, std::iter::once(own).chain(others))
+}
+
+/// Apply `(counter key, op, value, initial value)` steps in order, as
+/// described for [`apply_press`].
+fn apply_ops(
+    cx: &Context,
+    ctx_id: &str,
+    steps: impl IntoIterator<Item = (String, Op, i64, i64)>,
+) -> Outcome {
+    let steps: Vec<_> = steps
+        .into_iter()
+        .map(|(key, op, n, init)| (key, init, (op, n, init)))
+        .collect();
+    let ops: Vec<(Op, i64)> = steps.iter().map(|(_, _, (op, n, _))| (*op, *n)).collect();
+    let Some(updates) = apply_to_counters(cx, steps, |&(op, n, init), base, bounds| {
+        apply_bounded(op, base, n, init, bounds)
+    }) else {
+        return Outcome::Rejected;
+    };
+
+    let mut outcome = Outcome::Unchanged;
+    for (u, (op, n)) in updates.into_iter().zip(ops) {
+        if u.value == u.previous && !op.always_writes() {
+            continue;
+        }
+        outcome = Outcome::Applied;
+        cx.bus().publish_t(
+            COUNTER_CHANGED,
+            CounterChanged {
+                counter_key: u.key,
+                previous: Some(u.previous),
+                value: u.value,
+                op: op.describe(n),
+                kind: op.kind(),
+                source: ctx_id.to_string(),
+            },
+        );
+    }
+    outcome
 }
 
 // ── Math ────────────────────────────────────────────────────────────────────
 
-fn apply(op: Op, base: i64, n: i64, init: i64) -> i64 {
+pub fn apply(op: Op, base: i64, n: i64, init: i64) -> i64 {
     match op {
         Op::None => base,
         Op::Add => base.saturating_add(n),
@@ -285,5 +937,141 @@ fn apply(op: Op, base: i64, n: i64, init: i64) -> i64 {
         }
         Op::Reset => init,
         Op::Set => n,
+        Op::Modulo => base.checked_rem_euclid(n).unwrap_or(base),
+        Op::Power => match u32::try_from(n) {
+            Ok(exp) => base.saturating_pow(exp),
+            // A negative power isn't a whole number (except for ±1); ignore it.
+            Err(_) => base,
+        },
+        Op::AtLeast => base.max(n),
+        Op::AtMost => base.min(n),
+        Op::Random => match n {
+            0 => base,
+            1.. => fastrand::i64(1..=n),
+            _ => fastrand::i64(n..=-1),
+        },
+        Op::Toggle => {
+            if base == n { init } else { n }
+        }
+    }
+}
+
+/// [`apply`] followed by the counter's [`Bounds`]. `None` means the bounds
+/// rejected the result. `Op::None` never touches the value, even if it already
+/// sits outside freshly tightened bounds.
+pub fn apply_bounded(op: Op, base: i64, n: i64, init: i64, bounds: &Bounds) -> Option<i64> {
+    if matches!(op, Op::None) {
+        return Some(base);
+    }
+    bounds.enforce(apply(op, base, n, init))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{Op, Target, apply, dial_multiplier, parse_labels, parse_targets, repeat_interval};
+
+    #[test]
+    fn labels_split_on_commas_and_lines() {
+        assert_eq!(
+            parse_labels("Setup, Round 1,\nRound 2 ,, Finals\n"),
+            ["Setup", "Round 1", "Round 2", "Finals"]
+        );
+        assert!(parse_labels(" , ").is_empty());
+    }
+
+    #[test]
+    fn targets_parse_one_per_line() {
+        let target = |id: &str, op, value| Target {
+            counter_id: id.into(),
+            op,
+            value,
+        };
+        assert_eq!(
+            parse_targets("team score add\nattempts subtract 2\n\nbogus line\nx reset"),
+            [
+                target("team score", Op::Add, 1),
+                target("attempts", Op::Subtract, 2),
+                target("x", Op::Reset, 1),
+            ]
+        );
+        // An op with no counter ID isn't a target.
+        assert!(parse_targets("add 1").is_empty());
+    }
+
+    #[test]
+    fn dial_accelerates_with_sustained_spinning() {
+        assert_eq!(dial_multiplier(0), 1);
+        assert_eq!(dial_multiplier(3), 1);
+        assert_eq!(dial_multiplier(4), 2);
+        assert_eq!(dial_multiplier(10), 5);
+        assert_eq!(dial_multiplier(100), 10);
+    }
+
+    #[test]
+    fn modulo_stays_in_range_and_ignores_zero() {
+        assert_eq!(apply(Op::Modulo, 17, 5, 0), 2);
+        assert_eq!(apply(Op::Modulo, -3, 5, 0), 2);
+        assert_eq!(apply(Op::Modulo, 17, 0, 0), 17);
+        assert_eq!(apply(Op::Modulo, i64::MIN, -1, 0), i64::MIN);
+    }
+
+    #[test]
+    fn power_saturates() {
+        assert_eq!(apply(Op::Power, 3, 4, 0), 81);
+        assert_eq!(apply(Op::Power, 7, 0, 0), 1);
+        assert_eq!(apply(Op::Power, 10, 40, 0), i64::MAX);
+        assert_eq!(apply(Op::Power, -10, 41, 0), i64::MIN);
+        assert_eq!(apply(Op::Power, 5, -1, 0), 5);
+    }
+
+    #[test]
+    fn at_least_and_at_most() {
+        assert_eq!(apply(Op::AtLeast, 3, 10, 0), 10);
+        assert_eq!(apply(Op::AtLeast, 12, 10, 0), 12);
+        assert_eq!(apply(Op::AtMost, 12, 10, 0), 10);
+        assert_eq!(apply(Op::AtMost, 3, 10, 0), 3);
+    }
+
+    #[test]
+    fn random_rolls_within_the_die() {
+        for _ in 0..500 {
+            assert!((1..=6).contains(&apply(Op::Random, 0, 6, 0)));
+            assert!((-4..=-1).contains(&apply(Op::Random, 0, -4, 0)));
+        }
+        assert_eq!(apply(Op::Random, 9, 0, 0), 9);
+    }
+
+    #[test]
+    fn toggle_switches_between_value_and_initial() {
+        assert_eq!(apply(Op::Toggle, 0, 1, 0), 1);
+        assert_eq!(apply(Op::Toggle, 1, 1, 0), 0);
+        // Anything else snaps to the value first.
+        assert_eq!(apply(Op::Toggle, 7, 1, 0), 1);
+    }
+
+    #[test]
+    fn repeat_accelerates_then_holds() {
+        assert_eq!(
+            repeat_interval(Duration::ZERO, 4.0, 20.0),
+            Duration::from_millis(250)
+        );
+        assert_eq!(
+            repeat_interval(Duration::from_millis(1_000), 4.0, 20.0),
+            Duration::from_secs_f64(1.0 / 12.0)
+        );
+        assert_eq!(
+            repeat_interval(Duration::from_secs(60), 4.0, 20.0),
+            Duration::from_millis(50)
+        );
+    }
+
+    #[test]
+    fn repeat_rate_can_decelerate() {
+        assert!(
+            repeat_interval(Duration::from_secs(5), 10.0, 2.0)
+                > repeat_interval(Duration::ZERO, 10.0, 2.0)
+        );
     }
 }
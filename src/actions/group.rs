@@ -0,0 +1,135 @@
+//! GroupControlAction — applies one operation to every counter tagged with a
+//! group (set on each Counter key), e.g. to reset a whole scoreboard between
+//! rounds.
+//!
+//! All members are written in one store update and only then is
+//! `COUNTER_CHANGED` published for each of them, changed or not, so computed
+//! displays never recalculate against a half-applied group. Each counter's own
+//! bounds still apply; a counter whose bounds reject the result is left as it
+//! was.
+
+use serde_json::{Map, Value};
+use streamdeck_lib::prelude::*;
+
+use crate::actions::counter::{Op, apply_bounded};
+use crate::render::render_command;
+use crate::state::{apply_to_counters, group_members, is_published};
+use crate::topics::{COUNTER_CHANGED, CounterChanged};
+
+#[derive(Default)]
+pub struct GroupControlAction {
+    settings: GroupSettings,
+}
+
+impl ActionStatic for GroupControlAction {
+    const ID: &'static str = super::ids::GROUP;
+}
+
+impl Action for GroupControlAction {
+    fn id(&self) -> &str {
+        Self::ID
+    }
+
+    fn init(&mut self, cx: &Context, ctx_id: &str) {
+        cx.sd().get_settings(ctx_id);
+    }
+
+    fn did_receive_settings(&mut self, cx: &Context, ev: &incoming::DidReceiveSettings) {
+        self.settings = parse_settings(&ev.settings);
+        render(cx, ev.context, &self.settings);
+    }
+
+    fn key_down(&mut self, cx: &Context, ev: &incoming::KeyDown) {
+        self.settings = parse_settings(&ev.settings);
+        let s = &self.settings;
+        if s.group.is_empty() {
+            return;
+        }
+
+        // A member shadowed by a published value is read-only; skip it
+        // rather than reject the whole group.
+        let steps = group_members(cx, &s.group)
+            .into_iter()
+            .filter(|(key, _)| !is_published(cx, key))
+            .map(|(key, initial)| (key, initial, initial));
+        let updates = apply_to_counters(cx, steps, |&initial, base, bounds| {
+            apply_bounded(s.op, base, s.value, initial, bounds).or(Some(base))
+        })
+        .unwrap_or_default();
+
+        let op = format!("group {}: {}", s.group, s.op.describe(s.value));
+        for u in updates {
+            cx.bus().publish_t(
+                COUNTER_CHANGED,
+                CounterChanged {
+                    counter_key: u.key,
+                    previous: Some(u.previous),
+                    value: u.value,
+                    op: op.clone(),
+                    kind: s.op.kind(),
+                    source: ev.context.to_string(),
+                },
+            );
+        }
+        render(cx, ev.context, s);
+    }
+}
+
+fn render(cx: &Context, ctx_id: &str, s: &GroupSettings) {
+    let enabled = !s.group.is_empty() && !group_members(cx, &s.group).is_empty();
+    render_command(cx, ctx_id, &op_label(s.op, s.value), &s.group, enabled);
+}
+
+/// Short key label for the operation, e.g. `RESET`, `+1`, `=10`.
+fn op_label(op: Op, n: i64) -> String {
+    match op {
+        Op::None => "NONE".to_string(),
+        Op::Reset => "RESET".to_string(),
+        Op::Add => format!("+{n}"),
+        Op::Subtract => format!("-{n}"),
+        Op::Multiply => format!("x{n}"),
+        Op::Divide => format!("/{n}"),
+        Op::Set => format!("={n}"),
+        Op::Modulo => format!("%{n}"),
+        Op::Power => format!("^{n}"),
+        Op::AtLeast => format!(">={n}"),
+        Op::AtMost => format!("<={n}"),
+        Op::Random => format!("D{n}"),
+        Op::Toggle => format!("<>{n}"),
+    }
+}
+
+// ── Settings ─────────────────────────────────────────────────────────────────
+
+#[derive(Clone, Debug)]
+struct GroupSettings {
+    group: String,
+    op: Op,
+    value: i64,
+}
+
+impl Default for GroupSettings {
+    fn default() -> Self {
+        Self {
+            group: String::new(),
+            op: Op::Reset,
+            value: 0,
+        }
+    }
+}
+
+fn parse_settings(v: &Map<String, Value>) -> GroupSettings {
+    let group = v.get("group").and_then(|v| v.as_str()).unwrap_or("");
+    GroupSettings {
+        group: group.trim().to_string(),
+        op: v
+            .get("groupAction")
+            .and_then(|v| serde_json::from_value(v.clone()).ok())
+            .unwrap_or(Op::Reset),
+        value: match v.get("groupValue") {
+            Some(Value::Number(n)) => n.as_i64().unwrap_or(0),
+            Some(Value::String(s)) => s.trim().parse().unwrap_or(0),
+            _ => 0,
+        },
+    }
+}
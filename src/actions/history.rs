@@ -0,0 +1,160 @@
+//! UndoAction / RedoAction — step a shared counter through its history.
+//! The history itself lives in the state store next to `counters` (see
+//! `crate::state::undo_counter`); every `apply_to_counters` records into it, so
+//! presses, long presses and resets from any key can be reverted.
+//!
+//! Both keys target a counter by ID (per-key counters have no ID to target)
+//! and dim themselves while there is nothing to undo/redo.
+
+use serde_json::{Map, Value};
+use streamdeck_lib::prelude::*;
+
+use crate::render::render_command;
+use crate::state::{history_depth, redo_counter, undo_counter};
+use crate::topics::{COUNTER_CHANGED, ChangeKind, CounterChanged};
+
+#[derive(Default)]
+pub struct UndoAction {
+    target: String,
+}
+
+impl ActionStatic for UndoAction {
+    const ID: &'static str = super::ids::UNDO;
+}
+
+impl Action for UndoAction {
+    fn id(&self) -> &str {
+        Self::ID
+    }
+
+    fn topics(&self) -> &'static [&'static str] {
+        &[COUNTER_CHANGED.name]
+    }
+
+    fn init(&mut self, cx: &Context, ctx_id: &str) {
+        cx.sd().get_settings(ctx_id);
+    }
+
+    fn did_receive_settings(&mut self, cx: &Context, ev: &incoming::DidReceiveSettings) {
+        self.target = parse_settings(&ev.settings);
+        render(cx, ev.context, &self.target, Direction::Undo);
+    }
+
+    fn key_down(&mut self, cx: &Context, ev: &incoming::KeyDown) {
+        self.target = parse_settings(&ev.settings);
+        step(cx, ev.context, &self.target, Direction::Undo);
+    }
+
+    fn on_notify(&mut self, cx: &Context, ctx_id: &str, event: &ErasedTopic) {
+        if let Some(n) = event.downcast(COUNTER_CHANGED)
+            && n.counter_key == self.target
+        {
+            render(cx, ctx_id, &self.target, Direction::Undo);
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct RedoAction {
+    target: String,
+}
+
+impl ActionStatic for RedoAction {
+    const ID: &'static str = super::ids::REDO;
+}
+
+impl Action for RedoAction {
+    fn id(&self) -> &str {
+        Self::ID
+    }
+
+    fn topics(&self) -> &'static [&'static str] {
+        &[COUNTER_CHANGED.name]
+    }
+
+    fn init(&mut self, cx: &Context, ctx_id: &str) {
+        cx.sd().get_settings(ctx_id);
+    }
+
+    fn did_receive_settings(&mut self, cx: &Context, ev: &incoming::DidReceiveSettings) {
+        self.target = parse_settings(&ev.settings);
+        render(cx, ev.context, &self.target, Direction::Redo);
+    }
+
+    fn key_down(&mut self, cx: &Context, ev: &incoming::KeyDown) {
+        self.target = parse_settings(&ev.settings);
+        step(cx, ev.context, &self.target, Direction::Redo);
+    }
+
+    fn on_notify(&mut self, cx: &Context, ctx_id: &str, event: &ErasedTopic) {
+        if let Some(n) = event.downcast(COUNTER_CHANGED)
+            && n.counter_key == self.target
+        {
+            render(cx, ctx_id, &self.target, Direction::Redo);
+        }
+    }
+}
+
+// ── Shared ───────────────────────────────────────────────────────────────────
+
+#[derive(Clone, Copy)]
+enum Direction {
+    Undo,
+    Redo,
+}
+
+/// Apply one undo/redo step and notify every display of the counter. The
+/// notification also re-renders this key (and its sibling), since both
+/// subscribe to their target's `COUNTER_CHANGED`.
+fn step(cx: &Context, ctx_id: &str, target: &str, dir: Direction) {
+    if target.is_empty() {
+        return;
+    }
+    let value = match dir {
+        Direction::Undo => undo_counter(cx, target),
+        Direction::Redo => redo_counter(cx, target),
+    };
+    if let Some((previous, value)) = value {
+        let op = match dir {
+            Direction::Undo => "undo",
+            Direction::Redo => "redo",
+        };
+        cx.bus().publish_t(
+            COUNTER_CHANGED,
+            CounterChanged {
+                counter_key: target.to_string(),
+                previous: Some(previous),
+                value,
+                op: op.to_string(),
+                kind: ChangeKind::History,
+                source: ctx_id.to_string(),
+            },
+        );
+    }
+}
+
+fn render(cx: &Context, ctx_id: &str, target: &str, dir: Direction) {
+    let (undo, redo) = history_depth(cx, target);
+    let (text, available) = match dir {
+        Direction::Undo => ("UNDO", undo),
+        Direction::Redo => ("REDO", redo),
+    };
+    render_command(
+        cx,
+        ctx_id,
+        text,
+        target,
+        !target.is_empty() && available > 0,
+    );
+}
+
+// ── Settings ─────────────────────────────────────────────────────────────────
+
+/// Returns the target counter ID.
+fn parse_settings(v: &Map<String, Value>) -> String {
+    v.get("counterId")
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .trim()
+        .to_string()
+}
@@ -1,6 +1,12 @@
+pub mod backup;
 pub mod computed;
 pub mod counter;
+pub mod group;
+pub mod history;
+pub mod rate;
+pub mod stats;
 pub mod stopwatch;
+pub mod storage;
 pub mod timer;
 pub mod timer_adjust;
 
@@ -12,4 +18,9 @@ pub mod ids {
     pub const TIMER: &str = const_format::concatcp!(PLUGIN_ID, ".timer");
     pub const TIMER_ADJUST: &str = const_format::concatcp!(PLUGIN_ID, ".timeradjust");
     pub const STOPWATCH: &str = const_format::concatcp!(PLUGIN_ID, ".stopwatch");
+    pub const UNDO: &str = const_format::concatcp!(PLUGIN_ID, ".undo");
+    pub const REDO: &str = const_format::concatcp!(PLUGIN_ID, ".redo");
+    pub const GROUP: &str = const_format::concatcp!(PLUGIN_ID, ".group");
+    pub const RATE: &str = const_format::concatcp!(PLUGIN_ID, ".rate");
+    pub const STATS: &str = const_format::concatcp!(PLUGIN_ID, ".stats");
 }
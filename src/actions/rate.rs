@@ -0,0 +1,82 @@
+//! RateAction — thin shell showing how fast a shared counter is changing,
+//! e.g. "12/min" over the last 5 minutes. The change history and the decay
+//! tick live in `crate::adapters::rate::RateAdapter`; the action only tells
+//! it which counter, window and unit to show.
+
+use serde_json::{Map, Value};
+use streamdeck_lib::prelude::*;
+
+use crate::adapters::rate::MAX_WINDOW_SECS;
+use crate::topics::{RATE_CTL, RateControl};
+
+#[derive(Default)]
+pub struct RateAction;
+
+impl ActionStatic for RateAction {
+    const ID: &'static str = super::ids::RATE;
+}
+
+impl Action for RateAction {
+    fn id(&self) -> &str {
+        Self::ID
+    }
+
+    fn init(&mut self, cx: &Context, ctx_id: &str) {
+        cx.sd().get_settings(ctx_id);
+    }
+
+    fn teardown(&mut self, cx: &Context, ctx_id: &str) {
+        cx.bus().publish_t(
+            RATE_CTL,
+            RateControl::Unwatch {
+                ctx_id: ctx_id.to_string(),
+            },
+        );
+    }
+
+    fn did_receive_settings(&mut self, cx: &Context, ev: &incoming::DidReceiveSettings) {
+        let (counter_key, window_secs, per_secs) = parse_settings(&ev.settings);
+        let ctx_id = ev.context.to_string();
+        let ctl = if counter_key.is_empty() {
+            RateControl::Unwatch { ctx_id }
+        } else {
+            RateControl::Watch {
+                ctx_id,
+                counter_key,
+                window_secs,
+                per_secs,
+            }
+        };
+        cx.bus().publish_t(RATE_CTL, ctl);
+    }
+}
+
+// ── Settings ─────────────────────────────────────────────────────────────────
+
+/// Returns (counter ID, window in seconds, unit in seconds).
+fn parse_settings(v: &Map<String, Value>) -> (String, u64, u64) {
+    let counter_key = v
+        .get("counterId")
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .trim()
+        .to_string();
+
+    // Accept numbers or numeric strings, like the other PI fields.
+    let window_min = v
+        .get("rateWindow")
+        .and_then(|v| {
+            v.as_u64()
+                .or_else(|| v.as_str().and_then(|s| s.trim().parse().ok()))
+        })
+        .unwrap_or(5);
+    let window_secs = window_min.saturating_mul(60).clamp(60, MAX_WINDOW_SECS);
+
+    let per_secs = match v.get("rateUnit").and_then(|v| v.as_str()) {
+        Some("second") => 1,
+        Some("hour") => 3600,
+        _ => 60,
+    };
+
+    (counter_key, window_secs, per_secs)
+}
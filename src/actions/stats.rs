@@ -0,0 +1,101 @@
+//! StatsAction — shows one statistic of a shared counter: presses, high,
+//! low, when it last changed, or where it stood at session start. The stats
+//! themselves are kept by `crate::adapters::stats::StatsAdapter`; this key
+//! re-reads them whenever it announces a change.
+
+use chrono::{DateTime, Local};
+use serde_json::{Map, Value};
+use streamdeck_lib::prelude::*;
+
+use crate::render::render_stat;
+use crate::state::stats::Stat;
+use crate::state::{read_counter, read_stats};
+use crate::topics::STATS_CHANGED;
+
+#[derive(Default)]
+pub struct StatsAction {
+    counter_key: String,
+    stat: Stat,
+}
+
+impl ActionStatic for StatsAction {
+    const ID: &'static str = super::ids::STATS;
+}
+
+impl Action for StatsAction {
+    fn id(&self) -> &str {
+        Self::ID
+    }
+
+    fn topics(&self) -> &'static [&'static str] {
+        &[STATS_CHANGED.name]
+    }
+
+    fn init(&mut self, cx: &Context, ctx_id: &str) {
+        cx.sd().get_settings(ctx_id);
+    }
+
+    fn did_receive_settings(&mut self, cx: &Context, ev: &incoming::DidReceiveSettings) {
+        (self.counter_key, self.stat) = parse_settings(&ev.settings);
+        self.render(cx, ev.context);
+    }
+
+    fn on_notify(&mut self, cx: &Context, ctx_id: &str, event: &ErasedTopic) {
+        if let Some(msg) = event.downcast(STATS_CHANGED)
+            && msg.counter_key == self.counter_key
+        {
+            self.render(cx, ctx_id);
+        }
+    }
+}
+
+impl StatsAction {
+    fn render(&self, cx: &Context, ctx_id: &str) {
+        let label = format!("{} {}", self.counter_key, self.stat.label());
+        let text = match read_stats(cx, &self.counter_key) {
+            None => "--".to_string(),
+            Some(stats) if self.stat == Stat::Changed => stats
+                .changed_unix_ms
+                .and_then(DateTime::from_timestamp_millis)
+                .map_or_else(
+                    || "--".to_string(),
+                    |t| fmt_changed(t.with_timezone(&Local)),
+                ),
+            Some(stats) => {
+                let value = read_counter(cx, &self.counter_key, stats.session_start);
+                let n = stats.get(self.stat, value);
+                match self.stat {
+                    Stat::Session if n > 0 => format!("+{n}"),
+                    _ => n.to_string(),
+                }
+            }
+        };
+        render_stat(cx, ctx_id, &text, &label);
+    }
+}
+
+/// The time of day for a change today, otherwise its date.
+fn fmt_changed(t: DateTime<Local>) -> String {
+    if t.date_naive() == Local::now().date_naive() {
+        t.format("%H:%M").to_string()
+    } else {
+        t.format("%b %-d").to_string()
+    }
+}
+
+// ── Settings ─────────────────────────────────────────────────────────────────
+
+fn parse_settings(v: &Map<String, Value>) -> (String, Stat) {
+    let counter_key = v
+        .get("counterId")
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .trim()
+        .to_string();
+    let stat = v
+        .get("stat")
+        .and_then(|v| v.as_str())
+        .and_then(Stat::parse)
+        .unwrap_or_default();
+    (counter_key, stat)
+}
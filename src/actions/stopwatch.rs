@@ -49,16 +49,16 @@ impl Action for StopwatchAction {
     }
 
     fn did_receive_settings(&mut self, cx: &Context, ev: &incoming::DidReceiveSettings) {
-        self.long_press_ms = parse_settings(&ev.settings);
-        if !self.hello_sent {
+        let (long_press_ms, name) = parse_settings(&ev.settings);
+        self.long_press_ms = long_press_ms;
+        let ctx_id = ev.context.to_string();
+        let ctl = if self.hello_sent {
+            StopwatchControl::Rename { ctx_id, name }
+        } else {
             self.hello_sent = true;
-            cx.bus().publish_t(
-                STOPWATCH_CTL,
-                StopwatchControl::Hello {
-                    ctx_id: ev.context.to_string(),
-                },
-            );
-        }
+            StopwatchControl::Hello { ctx_id, name }
+        };
+        cx.bus().publish_t(STOPWATCH_CTL, ctl);
     }
 
     fn key_down(&mut self, cx: &Context, ev: &incoming::KeyDown) {
@@ -111,10 +111,18 @@ impl Action for StopwatchAction {
 
 // ── Settings ─────────────────────────────────────────────────────────────────
 
-fn parse_settings(v: &Map<String, Value>) -> u64 {
-    match v.get("longPressMs") {
+/// Returns (long_press_ms, stopwatch_name)
+fn parse_settings(v: &Map<String, Value>) -> (u64, String) {
+    let long_press_ms = match v.get("longPressMs") {
         Some(Value::Number(n)) => n.as_u64().unwrap_or(500),
         Some(Value::String(s)) => s.trim().parse().unwrap_or(500),
         _ => 500,
-    }
+    };
+    let name = v
+        .get("stopwatchName")
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .trim()
+        .to_string();
+    (long_press_ms, name)
 }
@@ -0,0 +1,54 @@
+//! Choosing where the plugin keeps its state, from the Counter property
+//! inspector (`sendToPlugin`). The PI asks for the current backend when it
+//! opens and gets a `storage` message back after every change.
+//!
+//! PI → plugin:
+//! `{ "event": "getStorage" }` or `{ "event": "setStorage", "backend": "globals" | "file" }`
+
+use serde_json::{Value, json};
+use streamdeck_lib::prelude::*;
+use tracing::{info, warn};
+
+use crate::state::store::{Backend, active_backend, switch_backend};
+
+/// Handle a PI message if it's a storage request; anything else is ignored.
+pub fn handle_pi_message(cx: &Context, ctx_id: &str, payload: &Value) {
+    let result = match payload.get("event").and_then(|v| v.as_str()) {
+        Some("getStorage") => Ok(String::new()),
+        Some("setStorage") => set(cx, payload),
+        _ => return,
+    };
+    let (ok, message) = match result {
+        Ok(msg) => (true, msg),
+        Err(msg) => {
+            warn!("storage: {msg}");
+            (false, msg)
+        }
+    };
+    cx.sd().send_to_property_inspector(
+        ctx_id,
+        json!({
+            "event": "storage",
+            "backend": active_backend(cx),
+            "ok": ok,
+            "message": message,
+        }),
+    );
+}
+
+fn set(cx: &Context, payload: &Value) -> Result<String, String> {
+    let backend: Backend = payload
+        .get("backend")
+        .cloned()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .ok_or("unknown storage backend")?;
+    if backend == active_backend(cx) {
+        return Ok(String::new());
+    }
+    switch_backend(cx, backend)?;
+    info!("storage: switched to {backend:?}");
+    Ok(match backend {
+        Backend::Globals => "moved state into Stream Deck settings".to_string(),
+        Backend::File => "moved state into state.json in the data folder".to_string(),
+    })
+}
@@ -24,6 +24,8 @@ pub struct TimerAction {
     // Cached so we can detect duration changes vs first-receive
     duration_ms: u64,
     long_press_ms: u64,
+    /// Whether this context is on a Stream Deck+ dial, from willAppear.
+    encoder: bool,
 }
 
 impl Default for TimerAction {
@@ -35,6 +37,7 @@ impl Default for TimerAction {
             long_fired_press_id: Arc::new(AtomicU64::new(0)),
             duration_ms: 0, // sentinel: 0 = uninitialized, first DidReceiveSettings sends Hello
             long_press_ms: 500,
+            encoder: false,
         }
     }
 }
@@ -52,6 +55,10 @@ impl Action for TimerAction {
         cx.sd().get_settings(_ctx_id);
     }
 
+    fn will_appear(&mut self, _cx: &Context, ev: &incoming::WillAppear) {
+        self.encoder = ev.controller == "Encoder";
+    }
+
     fn did_receive_settings(&mut self, cx: &Context, ev: &incoming::DidReceiveSettings) {
         let (duration_secs, long_press_ms, name) = parse_settings(&ev.settings);
         self.long_press_ms = long_press_ms;
@@ -66,6 +73,7 @@ impl Action for TimerAction {
                     ctx_id: ev.context.to_string(),
                     name,
                     duration_ms: new_duration_ms,
+                    encoder: self.encoder,
                 },
             );
         } else {
@@ -86,6 +94,38 @@ impl Action for TimerAction {
     // No teardown override — the adapter keeps the timer ticking off-screen.
 
     fn key_down(&mut self, cx: &Context, ev: &incoming::KeyDown) {
+        self.press(cx, ev.context);
+    }
+
+    fn key_up(&mut self, cx: &Context, ev: &incoming::KeyUp) {
+        self.release(cx, ev.context);
+    }
+
+    // On a Stream Deck+ dial, pressing works like the key and turning sets
+    // the duration of an idle timer.
+
+    fn dial_down(&mut self, cx: &Context, ev: &incoming::DialDown) {
+        self.press(cx, ev.context);
+    }
+
+    fn dial_up(&mut self, cx: &Context, ev: &incoming::DialUp) {
+        self.release(cx, ev.context);
+    }
+
+    fn dial_rotate(&mut self, cx: &Context, ev: &incoming::DialRotate) {
+        cx.bus().publish_t(
+            TIMER_CTL,
+            TimerControl::Turn {
+                ctx_id: ev.context.to_string(),
+                ticks: ev.ticks,
+            },
+        );
+    }
+}
+
+impl TimerAction {
+    /// Start a press epoch; holding past `long_press_ms` resets the timer.
+    fn press(&mut self, cx: &Context, ctx_id: &str) {
         self.holding.store(true, Ordering::SeqCst);
 
         self.press_seq = self.press_seq.wrapping_add(1);
@@ -97,7 +137,7 @@ impl Action for TimerAction {
         let active_id = Arc::clone(&self.active_press_id);
         let fired_id = Arc::clone(&self.long_fired_press_id);
         let cx2 = cx.clone();
-        let ctx = ev.context.to_string();
+        let ctx = ctx_id.to_string();
         let long_press_ms = self.long_press_ms;
 
         std::thread::spawn(move || {
@@ -116,7 +156,8 @@ impl Action for TimerAction {
         });
     }
 
-    fn key_up(&mut self, cx: &Context, ev: &incoming::KeyUp) {
+    /// End the press: a short one toggles start/pause.
+    fn release(&mut self, cx: &Context, ctx_id: &str) {
         self.holding.store(false, Ordering::SeqCst);
 
         let pid = self.active_press_id.load(Ordering::SeqCst);
@@ -128,7 +169,7 @@ impl Action for TimerAction {
         cx.bus().publish_t(
             TIMER_CTL,
             TimerControl::Toggle {
-                ctx_id: ev.context.to_string(),
+                ctx_id: ctx_id.to_string(),
             },
         );
     }
@@ -0,0 +1,139 @@
+//! ChangeLogAdapter — appends every change of a logged counter to
+//! `changes.jsonl` / `changes.csv` in the plugin's data directory. Logging is
+//! opt-in per counter (see `crate::state::changelog`). As a `COUNTER_CHANGED`
+//! subscriber it sees every write without the actions knowing it exists.
+//!
+//! Files rotate by size: the live file becomes `changes.1.<ext>`, the old
+//! `.1` becomes `.2`, and so on, keeping [`LOG_FILES_KEPT`] files in total.
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
+use std::time::Duration;
+
+use chrono::{Local, SecondsFormat};
+use crossbeam_channel::{Receiver, RecvTimeoutError};
+use streamdeck_lib::prelude::*;
+use tracing::warn;
+
+use crate::paths::data_dir;
+use crate::state::changelog::{LogFormat, LogRecord};
+use crate::state::read_log_format;
+use crate::topics::{COUNTER_CHANGED, CounterChanged};
+
+/// How often the idle loop wakes to check for cancellation.
+const POLL_MS: u64 = 500;
+/// Rotate once the live file grows past this.
+const MAX_LOG_BYTES: u64 = 1024 * 1024;
+/// Live file plus rotated ones.
+const LOG_FILES_KEPT: usize = 5;
+
+pub struct ChangeLogAdapter;
+
+impl AdapterStatic for ChangeLogAdapter {
+    const NAME: &'static str = "changelog_adapter";
+}
+
+impl Adapter for ChangeLogAdapter {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+    fn policy(&self) -> StartPolicy {
+        StartPolicy::Eager
+    }
+    fn topics(&self) -> &'static [&'static str] {
+        &[COUNTER_CHANGED.name]
+    }
+
+    fn start(
+        &self,
+        cx: &Context,
+        _bus: Arc<dyn Bus>,
+        rx: Receiver<Arc<ErasedTopic>>,
+    ) -> AdapterResult {
+        let cancel = Arc::new(AtomicBool::new(false));
+        let cancel_for_thread = Arc::clone(&cancel);
+        let cx = cx.clone();
+
+        let join = std::thread::spawn(move || {
+            let dir = data_dir();
+
+            loop {
+                match rx.recv_timeout(Duration::from_millis(POLL_MS)) {
+                    Ok(ev) => {
+                        if let Some(n) = ev.downcast(COUNTER_CHANGED)
+                            && let Some(dir) = &dir
+                            && let Some(format) = read_log_format(&cx, &n.counter_key)
+                            && let Err(e) = append(dir, format, n)
+                        {
+                            warn!("change log: could not write {}: {e}", n.counter_key);
+                        }
+                    }
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+                if cancel_for_thread.load(Ordering::Relaxed) {
+                    break;
+                }
+            }
+        });
+
+        Ok(AdapterHandle::from_thread(join, move || {
+            cancel.store(true, Ordering::Relaxed);
+        }))
+    }
+}
+
+/// Append one change to the log for `format`, rotating first if it's full.
+fn append(dir: &Path, format: LogFormat, n: &CounterChanged) -> std::io::Result<()> {
+    fs::create_dir_all(dir)?;
+    let path = log_path(dir, format, 0);
+    if fs::metadata(&path).is_ok_and(|m| m.len() >= MAX_LOG_BYTES) {
+        rotate(dir, format)?;
+    }
+
+    let ts = Local::now().to_rfc3339_opts(SecondsFormat::Millis, false);
+    let record = LogRecord {
+        ts: &ts,
+        key: &n.counter_key,
+        op: &n.op,
+        old: n.previous,
+        new: n.value,
+        ctx: &n.source,
+    };
+
+    let fresh = !path.exists();
+    let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+    if let (true, Some(header)) = (fresh, format.header()) {
+        writeln!(file, "{header}")?;
+    }
+    writeln!(file, "{}", record.line(format))
+}
+
+/// Shift `changes.N` to `changes.N+1`, dropping the oldest.
+fn rotate(dir: &Path, format: LogFormat) -> std::io::Result<()> {
+    let oldest = log_path(dir, format, LOG_FILES_KEPT - 1);
+    if oldest.exists() {
+        fs::remove_file(oldest)?;
+    }
+    for i in (0..LOG_FILES_KEPT - 1).rev() {
+        let from = log_path(dir, format, i);
+        if from.exists() {
+            fs::rename(from, log_path(dir, format, i + 1))?;
+        }
+    }
+    Ok(())
+}
+
+/// `changes.<ext>` for the live file, `changes.<n>.<ext>` for rotated ones.
+fn log_path(dir: &Path, format: LogFormat, n: usize) -> PathBuf {
+    let ext = format.extension();
+    match n {
+        0 => dir.join(format!("changes.{ext}")),
+        n => dir.join(format!("changes.{n}.{ext}")),
+    }
+}
@@ -0,0 +1,630 @@
+//! ComputedAdapter — owns every computed key's expression and evaluates
+//! them. Keys can publish their result as a read-only virtual counter that
+//! other expressions read like any counter, so the keys form a graph: an
+//! edge runs from the key publishing an ID to each key that reads it.
+//!
+//! The graph is ordered topologically whenever a key is defined. A change
+//! re-evaluates only the keys that read what changed, and everything
+//! downstream of them, in that order — so a key reading a published value
+//! renders once, after it is up to date, rather than flickering through a
+//! stale one. Keys on (or downstream of) a cycle aren't evaluated at all:
+//! each key on one gets its own cycle as its error, and each key behind one
+//! the cycle it reads from.
+//!
+//! A key's definition goes when the key does, and with it any value it
+//! published: subscribers are told the ID is back to whatever counter (or 0)
+//! sits under it. If two keys publish the same ID, the one defined last owns
+//! it.
+
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crossbeam_channel::{Receiver, RecvTimeoutError};
+use serde_json::{Map, Value};
+use streamdeck_lib::prelude::*;
+
+use crate::expr::{ClockFn, Env, Expr, Number};
+use crate::state::stats::Stat;
+use crate::state::{find_stopwatch, find_timer, read_counters, read_stats, set_published};
+use crate::topics::{
+    CLOCK_CHANGED, COMPUTED_CTL, COMPUTED_VALUE, COUNTER_CHANGED, ChangeKind, ComputedControl,
+    ComputedDef, ComputedValue, CounterChanged, GraphError, STATS_CHANGED,
+};
+
+/// How often the idle loop wakes to check for cancellation.
+const POLL_MS: u64 = 500;
+
+pub struct ComputedAdapter;
+
+impl AdapterStatic for ComputedAdapter {
+    const NAME: &'static str = "computed_adapter";
+}
+
+impl Adapter for ComputedAdapter {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+    fn policy(&self) -> StartPolicy {
+        StartPolicy::Eager
+    }
+    fn topics(&self) -> &'static [&'static str] {
+        &[
+            COMPUTED_CTL.name,
+            COUNTER_CHANGED.name,
+            STATS_CHANGED.name,
+            CLOCK_CHANGED.name,
+        ]
+    }
+
+    fn start(
+        &self,
+        cx: &Context,
+        _bus: Arc<dyn Bus>,
+        rx: Receiver<Arc<ErasedTopic>>,
+    ) -> AdapterResult {
+        let cancel = Arc::new(AtomicBool::new(false));
+        let cancel_for_thread = Arc::clone(&cancel);
+        let cx = cx.clone();
+
+        let join = std::thread::spawn(move || {
+            let mut graph = Graph::default();
+
+            loop {
+                match rx.recv_timeout(Duration::from_millis(POLL_MS)) {
+                    Ok(ev) => handle(&cx, &mut graph, &ev),
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+                if cancel_for_thread.load(Ordering::Relaxed) {
+                    break;
+                }
+            }
+        });
+
+        Ok(AdapterHandle::from_thread(join, move || {
+            cancel.store(true, Ordering::Relaxed);
+        }))
+    }
+}
+
+fn handle(cx: &Context, graph: &mut Graph, ev: &ErasedTopic) {
+    if let Some(ComputedControl::Define { ctx_id, def }) = ev.downcast(COMPUTED_CTL) {
+        let dropped = graph.define(ctx_id, def.clone());
+        set_published(cx, graph.owners.keys().cloned());
+        let all = graph.order.clone();
+        graph.evaluate(cx, &all);
+        let counters = read_counters(cx);
+        for (id, previous) in dropped {
+            let value = counters.get(&id).and_then(|v| v.as_i64()).unwrap_or(0);
+            if previous.whole() != value {
+                let change = CounterChanged {
+                    counter_key: id,
+                    previous: Some(previous.whole()),
+                    value,
+                    op: "computed".to_string(),
+                    kind: ChangeKind::Computed,
+                    source: String::new(),
+                };
+                cx.bus().publish_t(COUNTER_CHANGED, change);
+            }
+        }
+        for (ctx_id, reason) in &graph.errors {
+            let result = Err(reason.clone());
+            let ctx_id = ctx_id.clone();
+            cx.bus()
+                .publish_t(COMPUTED_VALUE, ComputedValue { ctx_id, result });
+        }
+    } else if let Some(n) = ev.downcast(COUNTER_CHANGED) {
+        // Our own published values were already followed downstream, and a
+        // counter sharing a published ID is shadowed by it.
+        if !graph.owners.contains_key(&n.counter_key) {
+            let seeds = graph.readers(|_, node| node.deps.contains(&n.counter_key));
+            graph.evaluate(cx, &seeds);
+        }
+    } else if let Some(n) = ev.downcast(STATS_CHANGED) {
+        let reads_stat = |dep: &String| split_stat(dep).is_some_and(|(k, _)| k == n.counter_key);
+        let seeds = graph.readers(|_, node| node.deps.iter().any(reads_stat));
+        graph.evaluate(cx, &seeds);
+    } else if let Some(n) = ev.downcast(CLOCK_CHANGED) {
+        // A running clock re-renders its readers at most once a second.
+        let sec = unix_now_ms() / 1000;
+        let mut due = HashSet::new();
+        for (ctx_id, node) in &mut graph.nodes {
+            if !node.clocks.contains(&n.name) || (n.tick && node.last_tick_sec == Some(sec)) {
+                continue;
+            }
+            node.last_tick_sec = Some(sec);
+            due.insert(ctx_id.clone());
+        }
+        let seeds = graph.readers(|ctx_id, _| due.contains(ctx_id));
+        graph.evaluate(cx, &seeds);
+    }
+}
+
+// ── Graph ────────────────────────────────────────────────────────────────────
+
+struct Node {
+    def: ComputedDef,
+    /// Counter IDs read, including the counter behind each statistic.
+    deps: HashSet<String>,
+    /// Timer and stopwatch names read.
+    clocks: HashSet<String>,
+    /// When it was last defined, to settle two keys publishing one ID.
+    seq: u64,
+    /// Unix second of the last evaluation for a clock tick.
+    last_tick_sec: Option<u64>,
+}
+
+impl Node {
+    fn new(def: ComputedDef, seq: u64) -> Self {
+        Self {
+            deps: collect_deps(&def.expr),
+            clocks: def.expr.clocks().into_iter().map(String::from).collect(),
+            def,
+            seq,
+            last_tick_sec: None,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Graph {
+    nodes: HashMap<String, Node>,
+    seq: u64,
+    /// Published ID → the key that owns it.
+    owners: HashMap<String, String>,
+    /// Evaluable keys, each after every key whose published value it reads.
+    order: Vec<String>,
+    /// Keys that can't be evaluated, and why.
+    errors: BTreeMap<String, GraphError>,
+    /// Current published values.
+    virtuals: HashMap<String, Number>,
+}
+
+impl Graph {
+    /// Add, replace or (with `None`) remove a key, and re-sort. Returns the
+    /// published values no key publishes any more, as they last were.
+    fn define(&mut self, ctx_id: &str, def: Option<ComputedDef>) -> Vec<(String, Number)> {
+        match def {
+            Some(def) => {
+                self.seq += 1;
+                self.nodes
+                    .insert(ctx_id.to_string(), Node::new(def, self.seq));
+            }
+            None => {
+                self.nodes.remove(ctx_id);
+            }
+        }
+        self.sort();
+        let live = |id: &String| {
+            self.owners
+                .get(id)
+                .is_some_and(|k| !self.errors.contains_key(k))
+        };
+        let dead: Vec<String> = self
+            .virtuals
+            .keys()
+            .filter(|id| !live(id))
+            .cloned()
+            .collect();
+        dead.into_iter()
+            .filter_map(|id| self.virtuals.remove_entry(&id))
+            .collect()
+    }
+
+    /// Work out owners, then order the keys with Kahn's algorithm. Whatever
+    /// is left unordered sits on a cycle or behind one; [`cycles`] tells
+    /// which.
+    fn sort(&mut self) {
+        let mut ids: Vec<&String> = self.nodes.keys().collect();
+        ids.sort();
+
+        self.owners.clear();
+        self.errors.clear();
+        for &ctx_id in &ids {
+            let Some(id) = &self.nodes[ctx_id].def.publish_as else {
+                continue;
+            };
+            let seq = self.nodes[ctx_id].seq;
+            match self.owners.get(id) {
+                Some(owner) if self.nodes[owner].seq > seq => {
+                    self.errors
+                        .insert(ctx_id.clone(), GraphError::Taken(id.clone()));
+                }
+                Some(owner) => {
+                    self.errors
+                        .insert(owner.clone(), GraphError::Taken(id.clone()));
+                    self.owners.insert(id.clone(), ctx_id.clone());
+                }
+                None => {
+                    self.owners.insert(id.clone(), ctx_id.clone());
+                }
+            }
+        }
+
+        // `incoming[k]`: how many keys `k` reads from that aren't placed yet.
+        let mut incoming: BTreeMap<&String, usize> = BTreeMap::new();
+        let mut readers: HashMap<&String, Vec<&String>> = HashMap::new();
+        for &ctx_id in &ids {
+            let mut sources: Vec<&String> = self.nodes[ctx_id]
+                .deps
+                .iter()
+                .filter_map(|dep| self.owners.get(dep))
+                .collect();
+            sources.sort();
+            sources.dedup();
+            incoming.insert(ctx_id, sources.len());
+            for source in sources {
+                readers.entry(source).or_default().push(ctx_id);
+            }
+        }
+
+        let mut ready: Vec<&String> = incoming
+            .iter()
+            .filter(|(_, n)| **n == 0)
+            .map(|(k, _)| *k)
+            .collect();
+        let mut order = Vec::new();
+        while let Some(ctx_id) = ready.pop() {
+            order.push(ctx_id.clone());
+            for reader in readers.get(ctx_id).into_iter().flatten() {
+                let n = incoming.get_mut(reader).unwrap();
+                *n -= 1;
+                if *n == 0 {
+                    ready.push(reader);
+                }
+            }
+        }
+
+        let placed: HashSet<&String> = order.iter().collect();
+        let stuck: Vec<&String> = ids.into_iter().filter(|k| !placed.contains(k)).collect();
+        let published = |k: &String| self.nodes[k].def.publish_as.clone().unwrap_or_default();
+        let mut errors = Vec::new();
+        // Where each stuck key's trouble comes from: the published ID on a
+        // cycle that it is part of or reads, nearest first.
+        let mut blame: HashMap<&String, String> = HashMap::new();
+        let mut queue = VecDeque::new();
+        for cycle in cycles(&stuck, &readers) {
+            let mut through: Vec<String> = cycle.iter().map(|k| published(k)).collect();
+            through.sort();
+            for &ctx_id in &cycle {
+                errors.push((ctx_id.clone(), GraphError::Cycle(through.clone())));
+                blame.insert(ctx_id, published(ctx_id));
+                queue.push_back(ctx_id);
+            }
+        }
+        while let Some(source) = queue.pop_front() {
+            for &reader in readers.get(source).into_iter().flatten() {
+                if !blame.contains_key(reader) {
+                    let id = blame[source].clone();
+                    errors.push((reader.clone(), GraphError::ReadsCycle(id.clone())));
+                    blame.insert(reader, id);
+                    queue.push_back(reader);
+                }
+            }
+        }
+        self.errors.extend(errors);
+        order.retain(|k| !self.errors.contains_key(k));
+        self.order = order;
+    }
+
+    /// The keys `seeds` picks, and every key downstream of them, in order.
+    fn readers(&self, seeds: impl Fn(&str, &Node) -> bool) -> Vec<String> {
+        let mut changed: HashSet<&str> = HashSet::new();
+        let mut out = Vec::new();
+        for ctx_id in &self.order {
+            let node = &self.nodes[ctx_id];
+            let upstream = node.deps.iter().any(|d| changed.contains(d.as_str()));
+            if !(upstream || seeds(ctx_id, node)) {
+                continue;
+            }
+            if let Some(id) = &node.def.publish_as {
+                changed.insert(id);
+            }
+            out.push(ctx_id.clone());
+        }
+        out
+    }
+
+    /// Evaluate `keys` (already in order), send each result to its key, and
+    /// announce the published values that changed once all are written.
+    fn evaluate(&mut self, cx: &Context, keys: &[String]) {
+        if keys.is_empty() {
+            return;
+        }
+        let counters = read_counters(cx);
+        let now_ms = unix_now_ms();
+        let mut changes = Vec::new();
+        for ctx_id in keys {
+            let node = &self.nodes[ctx_id];
+            let env = Snapshot {
+                cx,
+                counters: &counters,
+                virtuals: &self.virtuals,
+                missing: if node.def.missing_as_zero { 0 } else { 1 },
+                now_ms,
+            };
+            let value = node.def.expr.eval(node.def.arith, &env);
+            if let Some(id) = &node.def.publish_as {
+                // Subscribers see whole numbers, so a change that rounds to
+                // the same one isn't news to them.
+                let previous = self.virtuals.insert(id.clone(), value);
+                let previous = previous.map(Number::whole);
+                let rounded = value.whole();
+                if previous != Some(rounded) {
+                    changes.push(CounterChanged {
+                        counter_key: id.clone(),
+                        previous,
+                        value: rounded,
+                        op: "computed".to_string(),
+                        kind: ChangeKind::Computed,
+                        source: String::new(),
+                    });
+                }
+            }
+            let ctx_id = ctx_id.clone();
+            cx.bus().publish_t(
+                COMPUTED_VALUE,
+                ComputedValue {
+                    ctx_id,
+                    result: Ok(value),
+                },
+            );
+        }
+        for change in changes {
+            cx.bus().publish_t(COUNTER_CHANGED, change);
+        }
+    }
+}
+
+/// The cycles among `keys`: each strongly connected set of them (Tarjan's
+/// algorithm) that has more than one key, or one key reading itself. Edges
+/// run from a key to its `readers`.
+fn cycles<'a>(
+    keys: &[&'a String],
+    readers: &HashMap<&'a String, Vec<&'a String>>,
+) -> Vec<Vec<&'a String>> {
+    struct Tarjan<'a, 'g> {
+        readers: &'g HashMap<&'a String, Vec<&'a String>>,
+        within: HashSet<&'a String>,
+        index: HashMap<&'a String, usize>,
+        low: HashMap<&'a String, usize>,
+        stack: Vec<&'a String>,
+        on_stack: HashSet<&'a String>,
+        found: Vec<Vec<&'a String>>,
+    }
+
+    impl<'a> Tarjan<'a, '_> {
+        fn visit(&mut self, k: &'a String) {
+            let n = self.index.len();
+            self.index.insert(k, n);
+            self.low.insert(k, n);
+            self.stack.push(k);
+            self.on_stack.insert(k);
+            let next: Vec<&'a String> = self
+                .readers
+                .get(k)
+                .into_iter()
+                .flatten()
+                .copied()
+                .filter(|r| self.within.contains(r))
+                .collect();
+            for r in next {
+                let low = if !self.index.contains_key(r) {
+                    self.visit(r);
+                    self.low[r]
+                } else if self.on_stack.contains(r) {
+                    self.index[r]
+                } else {
+                    continue;
+                };
+                let low = low.min(self.low[k]);
+                self.low.insert(k, low);
+            }
+            if self.low[k] != self.index[k] {
+                return;
+            }
+            let mut scc = Vec::new();
+            while let Some(top) = self.stack.pop() {
+                self.on_stack.remove(top);
+                scc.push(top);
+                if top == k {
+                    break;
+                }
+            }
+            let reads_itself = self.readers.get(k).is_some_and(|r| r.contains(&k));
+            if scc.len() > 1 || reads_itself {
+                scc.sort();
+                self.found.push(scc);
+            }
+        }
+    }
+
+    let mut t = Tarjan {
+        readers,
+        within: keys.iter().copied().collect(),
+        index: HashMap::new(),
+        low: HashMap::new(),
+        stack: Vec::new(),
+        on_stack: HashSet::new(),
+        found: Vec::new(),
+    };
+    for &k in keys {
+        if !t.index.contains_key(k) {
+            t.visit(k);
+        }
+    }
+    t.found
+}
+
+// ── Evaluation ───────────────────────────────────────────────────────────────
+
+/// The counters an expression depends on: every ID it references, plus the
+/// counter behind each statistic.
+fn collect_deps(expr: &Expr) -> HashSet<String> {
+    let mut set = HashSet::new();
+    for name in expr.vars() {
+        if let Some((key, _)) = split_stat(name) {
+            set.insert(key.to_string());
+        }
+        set.insert(name.to_string());
+    }
+    set
+}
+
+/// `deaths.high` → `("deaths", Stat::High)`; `None` without a known stat.
+fn split_stat(name: &str) -> Option<(&str, Stat)> {
+    let (key, stat) = name.rsplit_once('.')?;
+    Some((key, Stat::parse(stat)?))
+}
+
+/// The values one evaluation sees: counters as of its start, published
+/// values as evaluated so far, clocks as of `now_ms`.
+struct Snapshot<'a> {
+    cx: &'a Context,
+    counters: &'a Map<String, Value>,
+    virtuals: &'a HashMap<String, Number>,
+    missing: i64,
+    now_ms: u64,
+}
+
+impl Snapshot<'_> {
+    /// A published value or counter, or for `counter.stat` one of its
+    /// statistics. An ID that really contains the dot wins over the
+    /// statistic.
+    fn lookup(&self, id: &str) -> Option<Number> {
+        if let Some(v) = self.value(id) {
+            return Some(v);
+        }
+        let (key, stat) = split_stat(id)?;
+        let value = self.value(key)?.whole();
+        Some(Number::Int(read_stats(self.cx, key)?.get(stat, value)))
+    }
+
+    fn value(&self, id: &str) -> Option<Number> {
+        let counter = || self.counters.get(id).and_then(|v| v.as_i64());
+        self.virtuals
+            .get(id)
+            .copied()
+            .or_else(|| counter().map(Number::Int))
+    }
+}
+
+impl Env for Snapshot<'_> {
+    fn var(&self, id: &str) -> Number {
+        self.lookup(id).unwrap_or(Number::Int(self.missing))
+    }
+
+    /// Whole seconds, like the clock keys show. An unknown name reads 0.
+    fn clock(&self, func: ClockFn, name: &str) -> i64 {
+        let now = self.now_ms;
+        let (timer, stopwatch) = (find_timer(self.cx, name), find_stopwatch(self.cx, name));
+        match func {
+            ClockFn::Remaining => timer.map_or(0, |t| t.remaining_at(now) / 1000) as i64,
+            ClockFn::Elapsed => stopwatch.map_or(0, |s| s.elapsed_at(now) / 1000) as i64,
+            ClockFn::Running => {
+                let timing = timer.is_some_and(|t| t.running_at(now));
+                let counting = stopwatch.is_some_and(|s| s.anchor_unix_ms.is_some());
+                i64::from(timing || counting)
+            }
+        }
+    }
+}
+
+fn unix_now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or(Duration::ZERO)
+        .as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Graph;
+    use crate::expr::{Arith, Number, parse};
+    use crate::topics::{ComputedDef, GraphError};
+
+    fn define(g: &mut Graph, ctx_id: &str, expr: &str, publish_as: Option<&str>) {
+        let def = ComputedDef {
+            expr: parse(expr).unwrap(),
+            arith: Arith::Integer,
+            missing_as_zero: true,
+            publish_as: publish_as.map(String::from),
+        };
+        g.define(ctx_id, Some(def));
+    }
+
+    fn position(g: &Graph, ctx_id: &str) -> usize {
+        g.order.iter().position(|k| k == ctx_id).unwrap()
+    }
+
+    #[test]
+    fn readers_come_after_what_they_read() {
+        let mut g = Graph::default();
+        define(&mut g, "show", "kd + total", None);
+        define(&mut g, "kd", "kills / deaths", Some("kd"));
+        define(&mut g, "total", "kd * 10 + assists", Some("total"));
+        assert!(g.errors.is_empty());
+        assert!(position(&g, "kd") < position(&g, "total"));
+        assert!(position(&g, "total") < position(&g, "show"));
+
+        // A change to `deaths` reaches everything; `assists` skips `kd`.
+        let deaths = g.readers(|_, n| n.deps.contains("deaths"));
+        assert_eq!(deaths, ["kd", "total", "show"]);
+        let assists = g.readers(|_, n| n.deps.contains("assists"));
+        assert_eq!(assists, ["total", "show"]);
+    }
+
+    #[test]
+    fn cycles_are_errors_for_every_key_behind_them() {
+        let mut g = Graph::default();
+        define(&mut g, "a", "b + 1", Some("a"));
+        define(&mut g, "b", "a.high * 2", Some("b"));
+        define(&mut g, "show", "a", Some("show"));
+        define(&mut g, "further", "show * 2", None);
+        define(&mut g, "other", "kills", Some("other"));
+        assert_eq!(g.order, ["other"]);
+        assert_eq!(g.errors["a"].to_string(), "cycle through 'a', 'b'");
+        assert_eq!(g.errors["b"].to_string(), "cycle through 'a', 'b'");
+        assert_eq!(
+            g.errors["show"].to_string(),
+            "reads 'a', which is on a cycle"
+        );
+        assert_eq!(g.errors["further"], GraphError::ReadsCycle("a".into()));
+
+        // A second cycle is reported on its own, not merged with the first.
+        define(&mut g, "self", "self + 1", Some("self"));
+        assert_eq!(g.errors["self"].to_string(), "cycle through 'self'");
+        assert_eq!(g.errors["a"].to_string(), "cycle through 'a', 'b'");
+
+        // Breaking the loop puts everything back in order.
+        g.define("b", None);
+        g.define("self", None);
+        assert!(g.errors.is_empty());
+        assert_eq!(g.order.len(), 4);
+    }
+
+    #[test]
+    fn the_last_key_to_publish_an_id_owns_it() {
+        let mut g = Graph::default();
+        define(&mut g, "old", "1", Some("x"));
+        define(&mut g, "new", "2", Some("x"));
+        assert_eq!(g.owners["x"], "new");
+        assert_eq!(g.errors["old"], GraphError::Taken("x".into()));
+        assert_eq!(g.order, ["new"]);
+    }
+
+    #[test]
+    fn removing_a_key_drops_its_published_value() {
+        let mut g = Graph::default();
+        define(&mut g, "kd", "1", Some("kd"));
+        g.virtuals.insert("kd".into(), Number::Int(4));
+        assert!(g.define("other", None).is_empty());
+        assert_eq!(g.define("kd", None), [("kd".to_string(), Number::Int(4))]);
+        assert!(g.virtuals.is_empty());
+    }
+}
@@ -0,0 +1,76 @@
+//! GoalAdapter — plays the "goal reached" chime. Subscribes to
+//! `COUNTER_CHANGED` rather than hooking each action, so every write (short
+//! press, long press, undo/redo, …) is covered, and a shared counter chimes
+//! once instead of once per key displaying it.
+
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
+use std::time::Duration;
+
+use crossbeam_channel::{Receiver, RecvTimeoutError};
+use streamdeck_lib::prelude::*;
+
+use crate::audio::Audio;
+use crate::state::read_goal;
+use crate::topics::COUNTER_CHANGED;
+
+/// How often the idle loop wakes to check for cancellation.
+const POLL_MS: u64 = 500;
+
+pub struct GoalAdapter;
+
+impl AdapterStatic for GoalAdapter {
+    const NAME: &'static str = "goal_adapter";
+}
+
+impl Adapter for GoalAdapter {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+    fn policy(&self) -> StartPolicy {
+        StartPolicy::Eager
+    }
+    fn topics(&self) -> &'static [&'static str] {
+        &[COUNTER_CHANGED.name]
+    }
+
+    fn start(
+        &self,
+        cx: &Context,
+        _bus: Arc<dyn Bus>,
+        rx: Receiver<Arc<ErasedTopic>>,
+    ) -> AdapterResult {
+        let cancel = Arc::new(AtomicBool::new(false));
+        let cancel_for_thread = Arc::clone(&cancel);
+        let cx = cx.clone();
+
+        let join = std::thread::spawn(move || {
+            let audio = Audio::new();
+
+            loop {
+                match rx.recv_timeout(Duration::from_millis(POLL_MS)) {
+                    Ok(ev) => {
+                        if let Some(n) = ev.downcast(COUNTER_CHANGED)
+                            && let Some(goal) = read_goal(&cx, &n.counter_key)
+                            && goal.beep
+                            && n.previous.is_some_and(|p| goal.crossed(p, n.value))
+                        {
+                            audio.play_goal_beep();
+                        }
+                    }
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+                if cancel_for_thread.load(Ordering::Relaxed) {
+                    break;
+                }
+            }
+        });
+
+        Ok(AdapterHandle::from_thread(join, move || {
+            cancel.store(true, Ordering::Relaxed);
+        }))
+    }
+}
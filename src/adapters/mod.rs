@@ -1,2 +1,9 @@
+pub mod changelog;
+pub mod computed;
+pub mod goal;
+pub mod rate;
+pub mod schedule;
+pub mod stats;
 pub mod stopwatch;
 pub mod timer;
+pub mod trigger;
@@ -0,0 +1,235 @@
+//! RateAdapter — owns the change history behind Rate keys and re-renders
+//! them as the rate decays. Like `TimerAdapter` it runs whether or not any
+//! Rate key is mounted: every `COUNTER_CHANGED` is recorded, so switching
+//! pages (or adding a Rate key mid-session) doesn't lose the window. Resets,
+//! sets and imports are jumps rather than activity, so they aren't counted.
+//!
+//! History is in memory only — a restart starts every rate from zero.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{Receiver, RecvTimeoutError};
+use streamdeck_lib::prelude::*;
+
+use crate::render::{fmt_rate, render_rate};
+use crate::topics::{COUNTER_CHANGED, ChangeKind, RATE_CTL, RateControl};
+
+/// Rates are recomputed (and re-rendered if the text changed) this often.
+const TICK_MS: u64 = 1_000;
+/// Longest window a Rate key can ask for; older changes are dropped.
+pub const MAX_WINDOW_SECS: u64 = 3_600;
+/// Per-counter cap, so a runaway auto-repeat can't grow a buffer unbounded.
+const MAX_EVENTS: usize = 4_096;
+
+pub struct RateAdapter;
+
+impl AdapterStatic for RateAdapter {
+    const NAME: &'static str = "rate_adapter";
+}
+
+impl Adapter for RateAdapter {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+    fn policy(&self) -> StartPolicy {
+        StartPolicy::Eager
+    }
+    fn topics(&self) -> &'static [&'static str] {
+        &[COUNTER_CHANGED.name, RATE_CTL.name]
+    }
+
+    fn start(
+        &self,
+        cx: &Context,
+        _bus: Arc<dyn Bus>,
+        rx: Receiver<Arc<ErasedTopic>>,
+    ) -> AdapterResult {
+        let cancel = Arc::new(AtomicBool::new(false));
+        let cancel_for_thread = Arc::clone(&cancel);
+        let cx = cx.clone();
+
+        let join = std::thread::spawn(move || {
+            let mut history: HashMap<String, RateBuffer> = HashMap::new();
+            let mut watchers: HashMap<String, Watcher> = HashMap::new();
+
+            loop {
+                match rx.recv_timeout(Duration::from_millis(TICK_MS)) {
+                    Ok(ev) => {
+                        if let Some(n) = ev.downcast(COUNTER_CHANGED) {
+                            if let Some(previous) = n.previous
+                                && counts_toward_rate(n.kind)
+                            {
+                                history
+                                    .entry(n.counter_key.clone())
+                                    .or_default()
+                                    .push(Instant::now(), n.value.saturating_sub(previous));
+                            }
+                        } else if let Some(ctl) = ev.downcast(RATE_CTL) {
+                            match ctl {
+                                RateControl::Watch {
+                                    ctx_id,
+                                    counter_key,
+                                    window_secs,
+                                    per_secs,
+                                } => {
+                                    let w = watchers.entry(ctx_id.clone()).or_default();
+                                    *w = Watcher {
+                                        counter_key: counter_key.clone(),
+                                        window: Duration::from_secs(*window_secs),
+                                        per_secs: *per_secs,
+                                        last_text: None,
+                                    };
+                                }
+                                RateControl::Unwatch { ctx_id } => {
+                                    watchers.remove(ctx_id);
+                                }
+                            }
+                        }
+                    }
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+                if cancel_for_thread.load(Ordering::Relaxed) {
+                    break;
+                }
+                render_all(&cx, &history, &mut watchers);
+            }
+        });
+
+        Ok(AdapterHandle::from_thread(join, move || {
+            cancel.store(true, Ordering::Relaxed);
+        }))
+    }
+}
+
+/// Whether a change of this kind is activity. Everything but a step (or a
+/// published value following steps) jumps the value, so a reset from 500
+/// would otherwise show as -500/min.
+fn counts_toward_rate(kind: ChangeKind) -> bool {
+    matches!(kind, ChangeKind::Step | ChangeKind::Computed)
+}
+
+/// A Rate key and what it last showed, to skip redundant `setImage` calls.
+#[derive(Default)]
+struct Watcher {
+    counter_key: String,
+    window: Duration,
+    per_secs: u64,
+    last_text: Option<String>,
+}
+
+fn render_all(
+    cx: &Context,
+    history: &HashMap<String, RateBuffer>,
+    watchers: &mut HashMap<String, Watcher>,
+) {
+    let now = Instant::now();
+    for (ctx_id, w) in watchers.iter_mut() {
+        let rate = history
+            .get(&w.counter_key)
+            .map_or(0.0, |b| b.rate(now, w.window, w.per_secs));
+        let text = fmt_rate(rate, w.per_secs);
+        if w.last_text.as_ref() != Some(&text) {
+            render_rate(cx, ctx_id, &text, &w.counter_key);
+            w.last_text = Some(text);
+        }
+    }
+}
+
+// ── Ring buffer ──────────────────────────────────────────────────────────────
+
+/// Recent changes of one counter, oldest first: when and by how much.
+#[derive(Default)]
+struct RateBuffer {
+    events: VecDeque<(Instant, i64)>,
+}
+
+impl RateBuffer {
+    fn push(&mut self, at: Instant, delta: i64) {
+        while self
+            .events
+            .front()
+            .is_some_and(|(t, _)| at.duration_since(*t).as_secs() > MAX_WINDOW_SECS)
+            || self.events.len() >= MAX_EVENTS
+        {
+            self.events.pop_front();
+        }
+        self.events.push_back((at, delta));
+    }
+
+    /// Net change over the last `window`, scaled to "per `per_secs`".
+    fn rate(&self, now: Instant, window: Duration, per_secs: u64) -> f64 {
+        if window.is_zero() {
+            return 0.0;
+        }
+        let total: i64 = self
+            .events
+            .iter()
+            .rev()
+            .take_while(|(t, _)| now.duration_since(*t) <= window)
+            .map(|(_, d)| *d)
+            .fold(0i64, i64::saturating_add);
+        total as f64 * per_secs as f64 / window.as_secs_f64()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::{ChangeKind, MAX_EVENTS, RateBuffer, counts_toward_rate};
+    use crate::actions::counter::Op;
+
+    #[test]
+    fn rate_scales_the_window_to_the_unit() {
+        let t0 = Instant::now();
+        let mut b = RateBuffer::default();
+        for i in 0..12 {
+            b.push(t0 + Duration::from_secs(i * 10), 1);
+        }
+        let now = t0 + Duration::from_secs(120);
+        // 12 increments in the last 5 minutes.
+        let rate = b.rate(now, Duration::from_secs(300), 60);
+        assert!((rate - 2.4).abs() < 1e-9);
+    }
+
+    #[test]
+    fn rate_decays_as_changes_leave_the_window() {
+        let t0 = Instant::now();
+        let mut b = RateBuffer::default();
+        b.push(t0, 5);
+        b.push(t0 + Duration::from_secs(50), -2);
+        let window = Duration::from_secs(60);
+        assert_eq!(b.rate(t0 + Duration::from_secs(55), window, 60), 3.0);
+        assert_eq!(b.rate(t0 + Duration::from_secs(100), window, 60), -2.0);
+        assert_eq!(b.rate(t0 + Duration::from_secs(200), window, 60), 0.0);
+    }
+
+    #[test]
+    fn jumps_do_not_count_toward_the_rate() {
+        for op in [Op::Add, Op::Subtract] {
+            assert!(counts_toward_rate(op.kind()), "{op:?}");
+        }
+        for op in [Op::Reset, Op::Set, Op::Multiply, Op::Random, Op::Toggle] {
+            assert!(!counts_toward_rate(op.kind()), "{op:?}");
+        }
+        assert!(counts_toward_rate(ChangeKind::Computed));
+        assert!(!counts_toward_rate(ChangeKind::History));
+        assert!(!counts_toward_rate(ChangeKind::Import));
+    }
+
+    #[test]
+    fn buffer_is_capped() {
+        let t0 = Instant::now();
+        let mut b = RateBuffer::default();
+        for _ in 0..MAX_EVENTS + 10 {
+            b.push(t0, 1);
+        }
+        assert_eq!(b.events.len(), MAX_EVENTS);
+    }
+}
@@ -0,0 +1,116 @@
+//! ScheduleAdapter — resets counters on their schedule (see
+//! `crate::state::schedule`). A schedule is in the store only while the key
+//! that set it is on screen.
+//!
+//! Catch-up: each counter's last reset time is persisted in the store, so a
+//! reset that fell due while the plugin was down, or the key off screen, is
+//! applied on the first check after it is back. The first time a schedule is seen, "now" is recorded
+//! instead, so setting one up never resets the counter straight away.
+
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
+use std::time::Duration;
+
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
+use crossbeam_channel::{Receiver, RecvTimeoutError};
+use streamdeck_lib::prelude::*;
+
+use crate::actions::counter::{Op, apply_bounded};
+use crate::state::{all_schedules, apply_to_counter, read_last_reset, write_last_reset};
+use crate::topics::{COUNTER_CHANGED, ChangeKind, CounterChanged};
+
+/// How often schedules are checked. Resets land within this of their time.
+const CHECK_MS: u64 = 15_000;
+
+pub struct ScheduleAdapter;
+
+impl AdapterStatic for ScheduleAdapter {
+    const NAME: &'static str = "schedule_adapter";
+}
+
+impl Adapter for ScheduleAdapter {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+    fn policy(&self) -> StartPolicy {
+        StartPolicy::Eager
+    }
+    fn topics(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    fn start(
+        &self,
+        cx: &Context,
+        _bus: Arc<dyn Bus>,
+        rx: Receiver<Arc<ErasedTopic>>,
+    ) -> AdapterResult {
+        let cancel = Arc::new(AtomicBool::new(false));
+        let cancel_for_thread = Arc::clone(&cancel);
+        let cx = cx.clone();
+
+        let join = std::thread::spawn(move || {
+            loop {
+                check_all(&cx);
+                match rx.recv_timeout(Duration::from_millis(CHECK_MS)) {
+                    Ok(_) | Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+                if cancel_for_thread.load(Ordering::Relaxed) {
+                    break;
+                }
+            }
+        });
+
+        Ok(AdapterHandle::from_thread(join, move || {
+            cancel.store(true, Ordering::Relaxed);
+        }))
+    }
+}
+
+fn check_all(cx: &Context) {
+    let now = Local::now();
+    for (key, record) in all_schedules(cx) {
+        let Some(last) = read_last_reset(cx, &key).and_then(local_from_unix_ms) else {
+            write_last_reset(cx, &key, now.timestamp_millis());
+            continue;
+        };
+        let Some(due) = record.schedule.due_since(last, now.naive_local()) else {
+            continue;
+        };
+        write_last_reset(cx, &key, unix_ms_from_local(due));
+
+        let update = apply_to_counter(cx, &key, record.initial, |base, bounds| {
+            apply_bounded(Op::Reset, base, 0, record.initial, bounds)
+        });
+        let Some(update) = update.filter(|u| u.value != u.previous) else {
+            continue;
+        };
+        cx.bus().publish_t(
+            COUNTER_CHANGED,
+            CounterChanged {
+                counter_key: key,
+                previous: Some(update.previous),
+                value: update.value,
+                op: "scheduled reset".to_string(),
+                kind: ChangeKind::Reset,
+                source: String::new(),
+            },
+        );
+    }
+}
+
+fn local_from_unix_ms(ms: i64) -> Option<NaiveDateTime> {
+    DateTime::from_timestamp_millis(ms).map(|t| t.with_timezone(&Local).naive_local())
+}
+
+/// Local wall-clock time to unix ms. A time skipped by a DST jump has no
+/// local instant; the hour after it is used instead.
+fn unix_ms_from_local(t: NaiveDateTime) -> i64 {
+    let local = |t: NaiveDateTime| Local.from_local_datetime(&t).earliest();
+    local(t)
+        .or_else(|| local(t + chrono::Duration::hours(1)))
+        .map_or_else(|| Local::now().timestamp_millis(), |d| d.timestamp_millis())
+}
@@ -0,0 +1,90 @@
+//! StatsAdapter — keeps each counter's statistics (`crate::state::stats`) up
+//! to date. Subscribes to `COUNTER_CHANGED` like the goal and trigger
+//! adapters, so every write is counted whichever key made it, and starts a
+//! new session for every counter when the plugin starts.
+
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
+use std::time::Duration;
+
+use chrono::Utc;
+use crossbeam_channel::{Receiver, RecvTimeoutError};
+use streamdeck_lib::prelude::*;
+
+use crate::state::{record_stats, start_stats_session};
+use crate::topics::{COUNTER_CHANGED, ChangeKind, CounterChanged, STATS_CHANGED, StatsChanged};
+
+/// How often the idle loop wakes to check for cancellation.
+const POLL_MS: u64 = 500;
+
+pub struct StatsAdapter;
+
+impl AdapterStatic for StatsAdapter {
+    const NAME: &'static str = "stats_adapter";
+}
+
+impl Adapter for StatsAdapter {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+    fn policy(&self) -> StartPolicy {
+        StartPolicy::Eager
+    }
+    fn topics(&self) -> &'static [&'static str] {
+        &[COUNTER_CHANGED.name]
+    }
+
+    fn start(
+        &self,
+        cx: &Context,
+        _bus: Arc<dyn Bus>,
+        rx: Receiver<Arc<ErasedTopic>>,
+    ) -> AdapterResult {
+        let cancel = Arc::new(AtomicBool::new(false));
+        let cancel_for_thread = Arc::clone(&cancel);
+        let cx = cx.clone();
+
+        let join = std::thread::spawn(move || {
+            for counter_key in start_stats_session(&cx) {
+                cx.bus()
+                    .publish_t(STATS_CHANGED, StatsChanged { counter_key });
+            }
+
+            loop {
+                match rx.recv_timeout(Duration::from_millis(POLL_MS)) {
+                    Ok(ev) => {
+                        if let Some(n) = ev.downcast(COUNTER_CHANGED) {
+                            record(&cx, n);
+                        }
+                    }
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+                if cancel_for_thread.load(Ordering::Relaxed) {
+                    break;
+                }
+            }
+        });
+
+        Ok(AdapterHandle::from_thread(join, move || {
+            cancel.store(true, Ordering::Relaxed);
+        }))
+    }
+}
+
+fn record(cx: &Context, n: &CounterChanged) {
+    // A press is a change some key made; an import comes from the backup
+    // buttons rather than the counter itself.
+    let pressed = !n.source.is_empty() && n.kind != ChangeKind::Import;
+    let now = Utc::now().timestamp_millis();
+    let previous = n.previous.unwrap_or(n.value);
+    record_stats(cx, &n.counter_key, previous, n.value, pressed, now);
+    cx.bus().publish_t(
+        STATS_CHANGED,
+        StatsChanged {
+            counter_key: n.counter_key.clone(),
+        },
+    );
+}
@@ -1,5 +1,6 @@
 //! StopwatchAdapter — owns stopwatch state and the tick thread.
-//! Mirror of TimerAdapter for elapsed (counts up, no expiry).
+//! Mirror of TimerAdapter for elapsed (counts up, no expiry), including the
+//! `CLOCK_CHANGED` announcements for named stopwatches.
 
 use std::collections::HashMap;
 use std::sync::{
@@ -9,11 +10,12 @@ use std::sync::{
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use crossbeam_channel::{Receiver, RecvTimeoutError};
-use serde_json::{Map, Value};
 use streamdeck_lib::prelude::*;
 
 use crate::render::render_time;
-use crate::topics::{STOPWATCH_CTL, StopwatchControl};
+use crate::state::clock::StopwatchRecord;
+use crate::state::{read_stopwatch, write_stopwatch};
+use crate::topics::{CLOCK_CHANGED, ClockChanged, STOPWATCH_CTL, StopwatchControl};
 
 const TICK_MS: u64 = 100;
 
@@ -72,6 +74,8 @@ impl Adapter for StopwatchAdapter {
 
 #[derive(Clone)]
 struct StopwatchEntry {
+    /// The Stopwatch Name expressions read it by. May be empty.
+    name: String,
     elapsed_ms: u64,
     /// Some(unix_ms) = running; elapsed_ms is as-of that instant.
     anchor_unix_ms: Option<u64>,
@@ -81,16 +85,13 @@ struct StopwatchEntry {
 fn handle_ctl(cx: &Context, state: &Mutex<HashMap<String, StopwatchEntry>>, ctl: &StopwatchControl) {
     let mut s = state.lock().unwrap();
     match ctl {
-        StopwatchControl::Hello { ctx_id } => {
+        StopwatchControl::Hello { ctx_id, name } => {
             if !s.contains_key(ctx_id) {
-                let entry = load_from_globals(cx, ctx_id).unwrap_or(StopwatchEntry {
-                    elapsed_ms: 0,
-                    anchor_unix_ms: None,
-                    last_rendered_sec: None,
-                });
+                let entry = load_saved(cx, ctx_id).unwrap_or_else(StopwatchEntry::fresh);
                 s.insert(ctx_id.clone(), entry);
             }
             let entry = s.get_mut(ctx_id).unwrap();
+            entry.name = name.clone();
             if let Some(anchor) = entry.anchor_unix_ms {
                 let extra = unix_now_ms().saturating_sub(anchor);
                 entry.elapsed_ms = entry.elapsed_ms.saturating_add(extra);
@@ -99,6 +100,15 @@ fn handle_ctl(cx: &Context, state: &Mutex<HashMap<String, StopwatchEntry>>, ctl:
             render_entry(cx, ctx_id, entry);
             persist(cx, ctx_id, entry);
         }
+        StopwatchControl::Rename { ctx_id, name } => {
+            if let Some(entry) = s.get_mut(ctx_id)
+                && entry.name != *name
+            {
+                let old_name = std::mem::replace(&mut entry.name, name.clone());
+                persist(cx, ctx_id, entry);
+                announce(cx, &old_name, false);
+            }
+        }
         StopwatchControl::Toggle { ctx_id } => {
             if let Some(entry) = s.get_mut(ctx_id) {
                 if let Some(anchor) = entry.anchor_unix_ms.take() {
@@ -120,6 +130,15 @@ fn handle_ctl(cx: &Context, state: &Mutex<HashMap<String, StopwatchEntry>>, ctl:
                 persist(cx, ctx_id, entry);
             }
         }
+        StopwatchControl::Reload => {
+            for (ctx_id, entry) in s.iter_mut() {
+                let name = std::mem::take(&mut entry.name);
+                *entry = load_saved(cx, ctx_id).unwrap_or_else(StopwatchEntry::fresh);
+                entry.name = name;
+                render_entry(cx, ctx_id, entry);
+                persist(cx, ctx_id, entry);
+            }
+        }
     }
 }
 
@@ -141,6 +160,18 @@ fn tick_all(cx: &Context, state: &Mutex<HashMap<String, StopwatchEntry>>) {
         let cur_sec = entry.elapsed_ms / 1000;
         if Some(cur_sec) != entry.last_rendered_sec {
             render_entry(cx, ctx_id, entry);
+            announce(cx, &entry.name, true);
+        }
+    }
+}
+
+impl StopwatchEntry {
+    fn fresh() -> Self {
+        Self {
+            name: String::new(),
+            elapsed_ms: 0,
+            anchor_unix_ms: None,
+            last_rendered_sec: None,
         }
     }
 }
@@ -152,33 +183,36 @@ fn render_entry(cx: &Context, ctx_id: &str, entry: &mut StopwatchEntry) {
     render_time(cx, ctx_id, secs, "", running);
 }
 
+/// Save the entry, then tell computed keys reading it by name.
 fn persist(cx: &Context, ctx_id: &str, entry: &StopwatchEntry) {
-    cx.globals().with_mut(|m| {
-        let map = m
-            .entry("stopwatches")
-            .or_insert_with(|| Value::Object(Map::new()))
-            .as_object_mut()
-            .unwrap();
-        let mut e = Map::new();
-        e.insert("elapsed_ms".into(), entry.elapsed_ms.into());
-        if let Some(anchor) = entry.anchor_unix_ms {
-            e.insert("anchor_unix_ms".into(), anchor.into());
-        }
-        map.insert(ctx_id.to_string(), Value::Object(e));
-    });
+    let record = StopwatchRecord {
+        elapsed_ms: entry.elapsed_ms,
+        anchor_unix_ms: entry.anchor_unix_ms,
+        name: entry.name.clone(),
+    };
+    write_stopwatch(cx, ctx_id, &record);
+    announce(cx, &entry.name, false);
+}
+
+fn announce(cx: &Context, name: &str, tick: bool) {
+    if !name.is_empty() {
+        let name = name.to_string();
+        cx.bus()
+            .publish_t(CLOCK_CHANGED, ClockChanged { name, tick });
+    }
 }
 
-fn load_from_globals(cx: &Context, ctx_id: &str) -> Option<StopwatchEntry> {
-    let stopwatches = cx.globals().get("stopwatches")?;
-    let v = stopwatches.get(ctx_id)?;
-    let mut elapsed_ms = v.get("elapsed_ms").and_then(|n| n.as_u64()).unwrap_or(0);
-    let mut anchor = v.get("anchor_unix_ms").and_then(|n| n.as_u64());
+fn load_saved(cx: &Context, ctx_id: &str) -> Option<StopwatchEntry> {
+    let saved = read_stopwatch(cx, ctx_id)?;
+    let mut elapsed_ms = saved.elapsed_ms;
+    let mut anchor = saved.anchor_unix_ms;
     if let Some(a) = anchor {
         let extra = unix_now_ms().saturating_sub(a);
         elapsed_ms = elapsed_ms.saturating_add(extra);
         anchor = Some(unix_now_ms());
     }
     Some(StopwatchEntry {
+        name: saved.name,
         elapsed_ms,
         anchor_unix_ms: anchor,
         last_rendered_sec: None,
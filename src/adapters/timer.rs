@@ -3,24 +3,27 @@
 //! TimerAction is mounted, so a timer that started on page A keeps running
 //! while the user is on page B and fires its expiry alert when it returns.
 //!
-//! Persistence: each entry is mirrored to `cx.globals()["timers"][ctx_id]`
-//! on every state transition (start/pause/reset/expire), so a full plugin
-//! restart can rehydrate via the saved `anchor_unix_ms`.
+//! Persistence: each entry is mirrored to a `TimerRecord` in the state store
+//! (`state::write_timer`) on every state transition (start/pause/reset/expire), so a full plugin
+//! restart can rehydrate via the saved `anchor_unix_ms`. Named timers also
+//! publish `CLOCK_CHANGED` on every transition and displayed second, for
+//! computed keys that read `remaining("name")`.
 
 use std::collections::HashMap;
 use std::sync::{
     Arc, Mutex,
     atomic::{AtomicBool, Ordering},
 };
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use crossbeam_channel::{Receiver, RecvTimeoutError};
-use serde_json::{Map, Value};
 use streamdeck_lib::prelude::*;
 
 use crate::audio::Audio;
-use crate::render::{render_expired, render_time};
-use crate::topics::{TIMER_CTL, TimerControl};
+use crate::render::{render_expired, render_time, render_time_dial};
+use crate::state::clock::TimerRecord;
+use crate::state::{read_timer, write_timer};
+use crate::topics::{CLOCK_CHANGED, ClockChanged, TIMER_CTL, TimerControl};
 
 const TICK_MS: u64 = 100;
 
@@ -29,6 +32,15 @@ const TICK_MS: u64 = 100;
 const MIN_DURATION_MS: u64 = 1_000;
 const MAX_DURATION_MS: u64 = 359_940_000; // 99:59:00 (hours:mins)
 
+/// Dial step sizes, picked by how soon after the previous tick a rotation
+/// arrives: `(max gap, step)`, fastest first. Slower turns use the last step.
+const TURN_STEPS: [(Duration, u64); 3] = [
+    (Duration::from_millis(60), 300_000),
+    (Duration::from_millis(150), 60_000),
+    (Duration::from_millis(300), 30_000),
+];
+const SLOW_TURN_STEP_MS: u64 = 5_000;
+
 pub struct TimerAdapter;
 
 impl AdapterStatic for TimerAdapter {
@@ -104,9 +116,14 @@ struct TimerEntry {
     /// Last second value rendered, to suppress redundant set_image calls.
     last_rendered_sec: Option<u64>,
     /// True only when expiry happened during plugin downtime (detected at
-    /// load_from_globals time). Audio plays fine off-screen, so the live
-    /// tick path does NOT set this — only the missed-while-down case does.
+    /// load_saved time). Audio plays fine off-screen, so the live tick path
+    /// does NOT set this — only the missed-while-down case does.
     beep_pending: bool,
+    /// When the dial last turned this timer, to pick the step size.
+    last_turn: Option<Instant>,
+    /// Whether the key is on a Stream Deck+ dial, so also gets touch-strip
+    /// feedback. Set by each Hello.
+    encoder: bool,
 }
 
 fn handle_ctl(
@@ -117,18 +134,24 @@ fn handle_ctl(
 ) {
     let mut s = state.lock().unwrap();
     match ctl {
-        TimerControl::Hello { ctx_id, name, duration_ms } => {
+        TimerControl::Hello {
+            ctx_id,
+            name,
+            duration_ms,
+            encoder,
+        } => {
             // If we already have an in-memory entry, that's authoritative
-            // (page switch — never lost it). Otherwise try globals, then
+            // (page switch — never lost it). Otherwise try the store, then
             // fall back to a fresh entry at full duration.
             if !s.contains_key(ctx_id) {
-                let entry = load_from_globals(cx, ctx_id)
-                    .unwrap_or_else(|| TimerEntry::fresh(*duration_ms));
+                let entry =
+                    load_saved(cx, ctx_id).unwrap_or_else(|| TimerEntry::fresh(*duration_ms));
                 s.insert(ctx_id.clone(), entry);
             }
             let entry = s.get_mut(ctx_id).unwrap();
             // Keep the routing name current (PI may have changed it).
             entry.name = name.clone();
+            entry.encoder = *encoder;
             // If the running anchor is stale (we slept), re-anchor.
             if let Some(anchor) = entry.anchor_unix_ms {
                 let elapsed = unix_now_ms().saturating_sub(anchor);
@@ -153,7 +176,7 @@ fn handle_ctl(
             let entry = s
                 .entry(ctx_id.clone())
                 .or_insert_with(|| TimerEntry::fresh(*duration_ms));
-            entry.name = name.clone();
+            let old_name = std::mem::replace(&mut entry.name, name.clone());
             // Compare against the configured (PI) duration — `duration_ms` may
             // have drifted from bumps and must not suppress a real PI change.
             if entry.configured_duration_ms != *duration_ms {
@@ -161,6 +184,12 @@ fn handle_ctl(
                 entry.name = name.clone();
                 render_entry(cx, ctx_id, entry);
                 persist(cx, ctx_id, entry);
+            } else if old_name != *name {
+                persist(cx, ctx_id, entry);
+            }
+            if old_name != *name {
+                // Expressions reading the old name now find nothing.
+                announce(cx, &old_name, false);
             }
         }
         TimerControl::Toggle { ctx_id } => {
@@ -205,13 +234,27 @@ fn handle_ctl(
                     .saturating_add(*delta_ms)
                     .clamp(MIN_DURATION_MS as i64, MAX_DURATION_MS as i64)
                     as u64;
-                if next == entry.duration_ms {
-                    continue;
-                }
-                entry.duration_ms = next;
-                entry.remaining_ms = next;
-                entry.last_rendered_sec = None;
-                // A bump on an expired timer revives it to a fresh idle state.
+                set_idle_duration(cx, ctx_id, entry, next);
+            }
+        }
+        TimerControl::Turn { ctx_id, ticks } => {
+            if let Some(entry) = s.get_mut(ctx_id)
+                && entry.anchor_unix_ms.is_none()
+            {
+                let now = Instant::now();
+                let gap = entry.last_turn.map(|t| now.duration_since(t));
+                entry.last_turn = Some(now);
+                let next = turned(entry.duration_ms, *ticks, turn_step_ms(gap));
+                set_idle_duration(cx, ctx_id, entry, next);
+            }
+        }
+        TimerControl::Reload => {
+            for (ctx_id, entry) in s.iter_mut() {
+                let name = std::mem::take(&mut entry.name);
+                *entry = load_saved(cx, ctx_id)
+                    .unwrap_or_else(|| TimerEntry::fresh(entry.configured_duration_ms));
+                entry.name = name;
+                // An imported timer that ran out in transit just shows DONE.
                 entry.beep_pending = false;
                 render_entry(cx, ctx_id, entry);
                 persist(cx, ctx_id, entry);
@@ -220,6 +263,46 @@ fn handle_ctl(
     }
 }
 
+/// Give an idle timer a new working duration (and a full countdown of it).
+fn set_idle_duration(cx: &Context, ctx_id: &str, entry: &mut TimerEntry, duration_ms: u64) {
+    if duration_ms == entry.duration_ms {
+        return;
+    }
+    entry.duration_ms = duration_ms;
+    entry.remaining_ms = duration_ms;
+    entry.last_rendered_sec = None;
+    // A bump on an expired timer revives it to a fresh idle state.
+    entry.beep_pending = false;
+    render_entry(cx, ctx_id, entry);
+    persist(cx, ctx_id, entry);
+}
+
+/// Dial step for a rotation `gap` after the previous one (`None` = first).
+fn turn_step_ms(gap: Option<Duration>) -> u64 {
+    let Some(gap) = gap else {
+        return SLOW_TURN_STEP_MS;
+    };
+    TURN_STEPS
+        .iter()
+        .find(|(max_gap, _)| gap < *max_gap)
+        .map_or(SLOW_TURN_STEP_MS, |(_, step)| *step)
+}
+
+/// `duration_ms` moved `ticks` steps of `step_ms`, landing on whole steps so
+/// a coarse turn from 1:05 goes to 1:30, not 1:35. Clamped to the timer range.
+fn turned(duration_ms: u64, ticks: i32, step_ms: u64) -> u64 {
+    let step = step_ms.max(1);
+    let n = u64::from(ticks.unsigned_abs());
+    let steps = if ticks >= 0 {
+        (duration_ms / step).saturating_add(n)
+    } else {
+        duration_ms.div_ceil(step).saturating_sub(n)
+    };
+    steps
+        .saturating_mul(step)
+        .clamp(MIN_DURATION_MS, MAX_DURATION_MS)
+}
+
 fn tick_all(cx: &Context, state: &Mutex<HashMap<String, TimerEntry>>, audio: &Audio) {
     let mut s = state.lock().unwrap();
     let now = unix_now_ms();
@@ -245,6 +328,7 @@ fn tick_all(cx: &Context, state: &Mutex<HashMap<String, TimerEntry>>, audio: &Au
             transitions.push(ctx_id.clone());
         } else if Some(cur_sec) != entry.last_rendered_sec {
             render_entry(cx, ctx_id, entry);
+            announce(cx, &entry.name, true);
         }
     }
 
@@ -267,6 +351,8 @@ impl TimerEntry {
             anchor_unix_ms: None,
             last_rendered_sec: None,
             beep_pending: false,
+            last_turn: None,
+            encoder: false,
         }
     }
 }
@@ -277,51 +363,51 @@ fn render_entry(cx: &Context, ctx_id: &str, entry: &mut TimerEntry) {
     if entry.remaining_ms == 0 && entry.anchor_unix_ms.is_none() {
         entry.last_rendered_sec = Some(0);
         render_expired(cx, ctx_id, &entry.name, entry.configured_duration_ms / 1000);
+        if entry.encoder {
+            render_time_dial(cx, ctx_id, None, &entry.name, 0.0);
+        }
         return;
     }
     let secs = entry.remaining_ms / 1000;
     entry.last_rendered_sec = Some(secs);
     let running = entry.anchor_unix_ms.is_some();
     render_time(cx, ctx_id, secs, &entry.name, running);
+    if entry.encoder {
+        let left = entry.remaining_ms as f32 / entry.duration_ms.max(1) as f32;
+        render_time_dial(cx, ctx_id, Some(secs), &entry.name, left);
+    }
 }
 
-// ── Globals persistence ──────────────────────────────────────────────────────
+// ── Persistence ──────────────────────────────────────────────────────────────
 
+/// Save the entry, then tell computed keys reading it by name.
 fn persist(cx: &Context, ctx_id: &str, entry: &TimerEntry) {
-    cx.globals().with_mut(|m| {
-        let map = m
-            .entry("timers")
-            .or_insert_with(|| Value::Object(Map::new()))
-            .as_object_mut()
-            .unwrap();
-        let mut e = Map::new();
-        e.insert("duration_ms".into(), entry.duration_ms.into());
-        e.insert(
-            "configured_duration_ms".into(),
-            entry.configured_duration_ms.into(),
-        );
-        e.insert("remaining_ms".into(), entry.remaining_ms.into());
-        if let Some(anchor) = entry.anchor_unix_ms {
-            e.insert("anchor_unix_ms".into(), anchor.into());
-        }
-        map.insert(ctx_id.to_string(), Value::Object(e));
-    });
+    let record = TimerRecord {
+        duration_ms: entry.duration_ms,
+        configured_duration_ms: Some(entry.configured_duration_ms),
+        remaining_ms: Some(entry.remaining_ms),
+        anchor_unix_ms: entry.anchor_unix_ms,
+        name: entry.name.clone(),
+    };
+    write_timer(cx, ctx_id, &record);
+    announce(cx, &entry.name, false);
 }
 
-fn load_from_globals(cx: &Context, ctx_id: &str) -> Option<TimerEntry> {
-    let timers = cx.globals().get("timers")?;
-    let v = timers.get(ctx_id)?;
-    let duration_ms = v.get("duration_ms").and_then(|n| n.as_u64())?;
+fn announce(cx: &Context, name: &str, tick: bool) {
+    if !name.is_empty() {
+        let name = name.to_string();
+        cx.bus()
+            .publish_t(CLOCK_CHANGED, ClockChanged { name, tick });
+    }
+}
+
+fn load_saved(cx: &Context, ctx_id: &str) -> Option<TimerEntry> {
+    let saved = read_timer(cx, ctx_id)?;
+    let duration_ms = saved.duration_ms;
     // Older saved timers predate the split — fall back to the working duration.
-    let configured_duration_ms = v
-        .get("configured_duration_ms")
-        .and_then(|n| n.as_u64())
-        .unwrap_or(duration_ms);
-    let mut remaining_ms = v
-        .get("remaining_ms")
-        .and_then(|n| n.as_u64())
-        .unwrap_or(duration_ms);
-    let mut anchor = v.get("anchor_unix_ms").and_then(|n| n.as_u64());
+    let configured_duration_ms = saved.configured_duration_ms.unwrap_or(duration_ms);
+    let mut remaining_ms = saved.remaining_ms.unwrap_or(duration_ms);
+    let mut anchor = saved.anchor_unix_ms;
     let mut beep_pending = false;
     if let Some(a) = anchor {
         let elapsed = unix_now_ms().saturating_sub(a);
@@ -336,13 +422,15 @@ fn load_from_globals(cx: &Context, ctx_id: &str) -> Option<TimerEntry> {
         }
     }
     Some(TimerEntry {
-        name: String::new(), // set by the next Hello on mount
+        name: saved.name, // refreshed by the next Hello on mount
         configured_duration_ms,
         duration_ms,
         remaining_ms,
         anchor_unix_ms: anchor,
         last_rendered_sec: None,
         beep_pending,
+        last_turn: None,
+        encoder: false,
     })
 }
 
@@ -352,3 +440,29 @@ fn unix_now_ms() -> u64 {
         .unwrap_or(Duration::ZERO)
         .as_millis() as u64
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{MAX_DURATION_MS, MIN_DURATION_MS, SLOW_TURN_STEP_MS, turn_step_ms, turned};
+
+    #[test]
+    fn faster_turns_take_coarser_steps() {
+        let slow = Some(Duration::from_secs(1));
+        assert_eq!(turn_step_ms(None), SLOW_TURN_STEP_MS);
+        assert_eq!(turn_step_ms(slow), SLOW_TURN_STEP_MS);
+        assert_eq!(turn_step_ms(Some(Duration::from_millis(200))), 30_000);
+        assert_eq!(turn_step_ms(Some(Duration::from_millis(100))), 60_000);
+        assert_eq!(turn_step_ms(Some(Duration::from_millis(20))), 300_000);
+    }
+
+    #[test]
+    fn turns_land_on_whole_steps() {
+        assert_eq!(turned(65_000, 1, 30_000), 90_000);
+        assert_eq!(turned(65_000, -1, 30_000), 60_000);
+        assert_eq!(turned(60_000, -2, 30_000), MIN_DURATION_MS);
+        assert_eq!(turned(60_000, 3, 5_000), 75_000);
+        assert_eq!(turned(MAX_DURATION_MS, 1, 300_000), MAX_DURATION_MS);
+    }
+}
@@ -0,0 +1,103 @@
+//! TriggerAdapter — fires a counter's threshold rules (see
+//! `crate::state::triggers`). Subscribes to `COUNTER_CHANGED`, so any write
+//! that publishes it — short or long press, auto-repeat, undo/redo — is
+//! covered without the actions knowing about triggers.
+//!
+//! A rule fires once per crossing: when the previous value did not satisfy
+//! it and the new one does. Its effects are a beep (played here) and a
+//! `COUNTER_TRIGGERED` event that counter keys flash on, and that presses
+//! the keys listening for the rule's `publish` name. Background colours aren't events — keys derive them from the
+//! current value when rendering.
+
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
+use std::time::Duration;
+
+use crossbeam_channel::{Receiver, RecvTimeoutError};
+use streamdeck_lib::prelude::*;
+
+use crate::audio::Audio;
+use crate::state::read_triggers;
+use crate::topics::{COUNTER_CHANGED, COUNTER_TRIGGERED, CounterChanged, CounterTriggered};
+
+/// How often the idle loop wakes to check for cancellation.
+const POLL_MS: u64 = 500;
+
+pub struct TriggerAdapter;
+
+impl AdapterStatic for TriggerAdapter {
+    const NAME: &'static str = "trigger_adapter";
+}
+
+impl Adapter for TriggerAdapter {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+    fn policy(&self) -> StartPolicy {
+        StartPolicy::Eager
+    }
+    fn topics(&self) -> &'static [&'static str] {
+        &[COUNTER_CHANGED.name]
+    }
+
+    fn start(
+        &self,
+        cx: &Context,
+        _bus: Arc<dyn Bus>,
+        rx: Receiver<Arc<ErasedTopic>>,
+    ) -> AdapterResult {
+        let cancel = Arc::new(AtomicBool::new(false));
+        let cancel_for_thread = Arc::clone(&cancel);
+        let cx = cx.clone();
+
+        let join = std::thread::spawn(move || {
+            let audio = Audio::new();
+
+            loop {
+                match rx.recv_timeout(Duration::from_millis(POLL_MS)) {
+                    Ok(ev) => {
+                        if let Some(n) = ev.downcast(COUNTER_CHANGED) {
+                            fire(&cx, &audio, n);
+                        }
+                    }
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+                if cancel_for_thread.load(Ordering::Relaxed) {
+                    break;
+                }
+            }
+        });
+
+        Ok(AdapterHandle::from_thread(join, move || {
+            cancel.store(true, Ordering::Relaxed);
+        }))
+    }
+}
+
+/// Run the effects of every rule this change crossed. At most one beep per
+/// change, however many rules asked for it.
+fn fire(cx: &Context, audio: &Audio, n: &CounterChanged) {
+    let mut beep = false;
+    for rule in read_triggers(cx, &n.counter_key) {
+        if !n.previous.is_some_and(|p| rule.crossed(p, n.value)) {
+            continue;
+        }
+        beep |= rule.beep;
+        if rule.flash || rule.publish.is_some() {
+            cx.bus().publish_t(
+                COUNTER_TRIGGERED,
+                CounterTriggered {
+                    counter_key: n.counter_key.clone(),
+                    event: rule.publish.unwrap_or_default(),
+                    flash: rule.flash,
+                },
+            );
+        }
+    }
+    if beep {
+        audio.play_trigger_beep();
+    }
+}
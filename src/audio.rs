@@ -1,5 +1,6 @@
-//! Tiny audio helper — emits a short synthesized two-tone beep on demand.
-//! Used by the timer adapter to signal expiry audibly.
+//! Tiny audio helper — emits short synthesized tones on demand.
+//! Used by the timer adapter to signal expiry audibly, and by the goal and
+//! trigger adapters when a counter crosses a goal or threshold.
 //!
 //! Each beep opens the system default output device fresh, plays, and drops
 //! the stream. This costs ~tens of ms per beep but means the plugin always
@@ -25,42 +26,57 @@ impl Audio {
     }
 
     /// Play a short two-tone "ding" — non-blocking, returns immediately.
-    /// Spawns a detached thread that owns the stream for the duration of
-    /// playback, then drops it.
     pub fn play_expiry_beep(&self) {
-        std::thread::spawn(|| {
-            let stream = match DeviceSinkBuilder::open_default_sink() {
-                Ok(s) => s,
-                Err(e) => {
-                    warn!("audio: could not open default output ({e}); skipping beep");
-                    return;
-                }
-            };
+        // 880 Hz (A5), then 1175 Hz (D6) 200ms later.
+        play_tones(&[(880.0, 0, 140, 40), (1175.0, 200, 180, 60)]);
+    }
 
-            let mixer = stream.mixer();
+    /// Play a rising three-note "goal reached" chime — non-blocking. Brighter
+    /// than the expiry beep so the two can't be confused.
+    pub fn play_goal_beep(&self) {
+        // C6, E6, G6 — a major arpeggio.
+        play_tones(&[
+            (1047.0, 0, 110, 36),
+            (1319.0, 120, 110, 36),
+            (1568.0, 240, 220, 73),
+        ]);
+    }
 
-            // First tone: 880 Hz (A5)
-            let beep1 = SineWave::new(880.0)
-                .take_duration(Duration::from_millis(140))
-                .fade_in(Duration::from_millis(10))
-                .fade_out(Duration::from_millis(40))
-                .amplify(0.25);
-            mixer.add(beep1);
+    /// Play a quick double blip for a counter threshold trigger — non-blocking.
+    pub fn play_trigger_beep(&self) {
+        play_tones(&[(988.0, 0, 80, 26), (988.0, 130, 80, 26)]);
+    }
+}
+
+/// Play `(frequency_hz, start_ms, length_ms, fade_out_ms)` tones on a
+/// detached thread that owns the stream for the duration of playback, then
+/// drops it.
+fn play_tones(tones: &'static [(f32, u64, u64, u64)]) {
+    std::thread::spawn(move || {
+        let stream = match DeviceSinkBuilder::open_default_sink() {
+            Ok(s) => s,
+            Err(e) => {
+                warn!("audio: could not open default output ({e}); skipping beep");
+                return;
+            }
+        };
 
-            // Second tone: 1175 Hz (D6), starting 200ms later
-            let beep2 = SineWave::new(1175.0)
-                .take_duration(Duration::from_millis(180))
+        let mixer = stream.mixer();
+        for &(freq, start_ms, len_ms, fade_ms) in tones {
+            let tone = SineWave::new(freq)
+                .take_duration(Duration::from_millis(len_ms))
                 .fade_in(Duration::from_millis(10))
-                .fade_out(Duration::from_millis(60))
+                .fade_out(Duration::from_millis(fade_ms))
                 .amplify(0.25)
-                .delay(Duration::from_millis(200));
-            mixer.add(beep2);
+                .delay(Duration::from_millis(start_ms));
+            mixer.add(tone);
+        }
 
-            debug!("audio: beep queued");
+        debug!("audio: beep queued");
 
-            // Hold the stream alive until playback finishes. Total length is
-            // 200ms (delay) + 180ms (beep2) = 380ms; pad a bit for fade-out tail.
-            std::thread::sleep(Duration::from_millis(450));
-        });
-    }
+        // Hold the stream alive until the last tone ends; pad a bit for the
+        // fade-out tail.
+        let end_ms = tones.iter().map(|&(_, s, l, _)| s + l).max().unwrap_or(0);
+        std::thread::sleep(Duration::from_millis(end_ms + 70));
+    });
 }
@@ -0,0 +1,927 @@
+//! The Computed Display expression language: a tokenizer and a
+//! recursive-descent parser producing an [`Expr`] tree, and its evaluator.
+//!
+//! Parsing is strict — an unknown character, an unbalanced parenthesis or a
+//! dangling operator is a [`ParseError`] with the position it was found at,
+//! rather than a quietly wrong number.
+//!
+//! ```text
+//! expr    := and (("or" | "||") and)*
+//! and     := not (("and" | "&&") not)*
+//! not     := ("not" | "!") not | compare
+//! compare := sum (("<" | "<=" | ">" | ">=" | "==" | "!=") sum)?
+//! sum     := term (("+" | "-") term)*
+//! term    := unary (("*" | "/") unary)*
+//! unary   := "-" unary | primary
+//! primary := number | name | string | name "(" args ")" | "(" expr ")"
+//! ```
+//!
+//! Names are counter IDs (`A`, `kills`, `deaths.high` for a statistic);
+//! quoted strings and `var("…")` reference IDs with other characters in them,
+//! or that clash with `and` / `or` / `not`. `remaining("…")`, `elapsed("…")`
+//! and `running("…")` read a named timer or stopwatch. Comparisons and logic
+//! give 1 for true and 0 for false; any non-zero value counts as true.
+//!
+//! Numbers may have a fractional part (`0.5`). Evaluation is in one of two
+//! [`Arith`] modes: `Integer` keeps the original exact whole-number
+//! behaviour (saturating, `/` truncates, a fraction is dropped where it is
+//! read), while `Decimal` runs in floating point and leaves fractions for
+//! the display to round.
+
+use std::fmt;
+
+// ── Errors ───────────────────────────────────────────────────────────────────
+
+/// Why an expression didn't parse, and where.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    /// Byte offset into the expression.
+    pub pos: usize,
+    pub reason: String,
+}
+
+impl ParseError {
+    fn new(pos: usize, reason: impl Into<String>) -> Self {
+        Self {
+            pos,
+            reason: reason.into(),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at column {}", self.reason, self.pos + 1)
+    }
+}
+
+// ── Tokenizer ────────────────────────────────────────────────────────────────
+
+#[derive(Clone, Debug, PartialEq)]
+enum Tok {
+    Num(Number),
+    Name(String),
+    Str(String),
+    Op(&'static str),
+    LParen,
+    RParen,
+    Comma,
+    End,
+}
+
+impl Tok {
+    fn describe(&self) -> String {
+        match self {
+            Tok::Num(n) => format!("number {n}"),
+            Tok::Name(s) => format!("name '{s}'"),
+            Tok::Str(s) => format!("\"{s}\""),
+            Tok::Op(op) => format!("'{op}'"),
+            Tok::LParen => "'('".to_string(),
+            Tok::RParen => "')'".to_string(),
+            Tok::Comma => "','".to_string(),
+            Tok::End => "end of expression".to_string(),
+        }
+    }
+}
+
+/// Operator spellings, longest first so `<=` isn't read as `<` then `=`.
+const OPERATORS: &[&str] = &[
+    "<=", ">=", "==", "!=", "&&", "||", "+", "-", "*", "/", "<", ">", "!",
+];
+
+/// Split `expr` into tokens, each with its byte offset. Always ends with
+/// [`Tok::End`].
+fn tokenize(expr: &str) -> Result<Vec<(Tok, usize)>, ParseError> {
+    let bytes = expr.as_bytes();
+    let mut toks = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let c = bytes[i];
+        let start = i;
+        let tok = match c {
+            b' ' | b'\t' | b'\r' | b'\n' => {
+                i += 1;
+                continue;
+            }
+            b'0'..=b'9' => {
+                let digits = |i: &mut usize| {
+                    while *i < bytes.len() && bytes[*i].is_ascii_digit() {
+                        *i += 1;
+                    }
+                };
+                digits(&mut i);
+                if bytes.get(i) == Some(&b'.') {
+                    i += 1;
+                    if !bytes.get(i).is_some_and(u8::is_ascii_digit) {
+                        return Err(ParseError::new(i, "expected digits after '.'"));
+                    }
+                    digits(&mut i);
+                }
+                // Only digits and at most one dot, so the only way for this
+                // not to parse is a whole number too big for an i64.
+                let text = &expr[start..i];
+                Tok::Num(if text.contains('.') {
+                    Number::Dec(text.parse().unwrap_or(0.0))
+                } else {
+                    Number::Int(text.parse().unwrap_or(i64::MAX))
+                })
+            }
+            b'"' | b'\'' | b'`' => {
+                let end = expr[i + 1..]
+                    .find(c as char)
+                    .ok_or_else(|| ParseError::new(start, "unterminated string"))?;
+                i += end + 2;
+                Tok::Str(expr[start + 1..start + 1 + end].to_string())
+            }
+            b'_' | b'a'..=b'z' | b'A'..=b'Z' => {
+                while i < bytes.len() {
+                    let ch = bytes[i];
+                    // `deaths.high` — a dot followed by a letter continues
+                    // the name, for counter statistics.
+                    let stat_dot =
+                        ch == b'.' && bytes.get(i + 1).is_some_and(u8::is_ascii_alphabetic);
+                    if ch.is_ascii_alphanumeric() || ch == b'_' || stat_dot {
+                        i += 1;
+                    } else {
+                        break;
+                    }
+                }
+                Tok::Name(expr[start..i].to_string())
+            }
+            b'+' | b'-' | b'*' | b'/' | b'<' | b'>' | b'=' | b'!' | b'&' | b'|' => {
+                let op = OPERATORS
+                    .iter()
+                    .find(|op| expr[i..].starts_with(**op))
+                    .ok_or_else(|| match c {
+                        b'=' => ParseError::new(start, "use '==' to compare"),
+                        _ => ParseError::new(start, format!("unexpected '{}'", c as char)),
+                    })?;
+                i += op.len();
+                Tok::Op(op)
+            }
+            b'(' => {
+                i += 1;
+                Tok::LParen
+            }
+            b')' => {
+                i += 1;
+                Tok::RParen
+            }
+            b',' => {
+                i += 1;
+                Tok::Comma
+            }
+            _ => {
+                let ch = expr[i..].chars().next().unwrap_or('?');
+                return Err(ParseError::new(start, format!("unexpected '{ch}'")));
+            }
+        };
+        toks.push((tok, start));
+    }
+    toks.push((Tok::End, expr.len()));
+    Ok(toks)
+}
+
+// ── Parser ───────────────────────────────────────────────────────────────────
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+    And,
+    Or,
+}
+
+/// Built-in functions (besides `var`, which is resolved while parsing).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Func {
+    Min,
+    Max,
+    Abs,
+    Clamp,
+    Round,
+    Sign,
+    If,
+}
+
+impl Func {
+    fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "min" => Some(Func::Min),
+            "max" => Some(Func::Max),
+            "abs" => Some(Func::Abs),
+            "clamp" => Some(Func::Clamp),
+            "round" => Some(Func::Round),
+            "sign" => Some(Func::Sign),
+            "if" => Some(Func::If),
+            _ => None,
+        }
+    }
+
+    /// Smallest and largest number of arguments.
+    fn arity(self) -> (usize, usize) {
+        match self {
+            Func::Min | Func::Max => (2, usize::MAX),
+            Func::Abs | Func::Sign => (1, 1),
+            Func::Round => (1, 2),
+            Func::Clamp | Func::If => (3, 3),
+        }
+    }
+}
+
+/// How `/` and the final result treat fractions.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Arith {
+    /// Exact whole numbers that saturate rather than overflow: `7 / 2` is 3,
+    /// and `0.5` is 0.
+    #[default]
+    Integer,
+    /// Fractions are kept: `7 / 2` is 3.5.
+    Decimal,
+}
+
+/// Readings of a named timer or stopwatch.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClockFn {
+    /// A timer's seconds left.
+    Remaining,
+    /// A stopwatch's seconds counted.
+    Elapsed,
+    /// 1 while a timer or stopwatch of that name is running.
+    Running,
+}
+
+impl ClockFn {
+    fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "remaining" => Some(ClockFn::Remaining),
+            "elapsed" => Some(ClockFn::Elapsed),
+            "running" => Some(ClockFn::Running),
+            _ => None,
+        }
+    }
+}
+
+/// A value: a literal, a counter, or what an expression evaluated to.
+/// Counters and integer-mode results are whole; decimal-mode ones needn't be.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Number {
+    Int(i64),
+    Dec(f64),
+}
+
+impl Number {
+    /// As the nearest whole number, halves away from zero.
+    pub fn whole(self) -> i64 {
+        match self {
+            Number::Int(n) => n,
+            Number::Dec(x) => x.round() as i64,
+        }
+    }
+
+    pub fn to_f64(self) -> f64 {
+        match self {
+            Number::Int(n) => n as f64,
+            Number::Dec(x) => x,
+        }
+    }
+}
+
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Number::Int(n) => write!(f, "{n}"),
+            Number::Dec(x) => write!(f, "{x}"),
+        }
+    }
+}
+
+/// Where an expression's values come from.
+pub trait Env {
+    /// A counter (or `counter.stat`) by ID.
+    fn var(&self, id: &str) -> Number;
+    /// A reading of the timer or stopwatch called `name`, in whole seconds.
+    fn clock(&self, func: ClockFn, name: &str) -> i64;
+}
+
+/// A parsed expression.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr {
+    Num(Number),
+    /// A counter (or `counter.stat`) by ID.
+    Var(String),
+    Neg(Box<Expr>),
+    Not(Box<Expr>),
+    Bin(BinOp, Box<Expr>, Box<Expr>),
+    Call(Func, Vec<Expr>),
+    /// `remaining("round")` and friends.
+    Clock(ClockFn, String),
+}
+
+/// Parse a whole expression.
+pub fn parse(expr: &str) -> Result<Expr, ParseError> {
+    let mut p = Parser {
+        toks: tokenize(expr)?,
+        i: 0,
+    };
+    let e = p.expr()?;
+    match p.peek() {
+        Tok::End => Ok(e),
+        Tok::RParen => Err(p.error("unmatched ')'")),
+        t => Err(p.error(format!("expected an operator, found {}", t.describe()))),
+    }
+}
+
+struct Parser {
+    toks: Vec<(Tok, usize)>,
+    i: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &Tok {
+        &self.toks[self.i].0
+    }
+
+    fn pos(&self) -> usize {
+        self.toks[self.i].1
+    }
+
+    fn next(&mut self) -> Tok {
+        let t = self.toks[self.i].0.clone();
+        if t != Tok::End {
+            self.i += 1;
+        }
+        t
+    }
+
+    fn error(&self, reason: impl Into<String>) -> ParseError {
+        ParseError::new(self.pos(), reason)
+    }
+
+    fn expect(&mut self, tok: Tok) -> Result<(), ParseError> {
+        if *self.peek() == tok {
+            self.next();
+            Ok(())
+        } else {
+            let found = self.peek().describe();
+            Err(self.error(format!("expected {}, found {found}", tok.describe())))
+        }
+    }
+
+    /// Whether the next token is the keyword `word` (any case).
+    fn at_keyword(&self, word: &str) -> bool {
+        matches!(self.peek(), Tok::Name(n) if n.eq_ignore_ascii_case(word))
+    }
+
+    fn expr(&mut self) -> Result<Expr, ParseError> {
+        let mut lhs = self.and()?;
+        while self.at_keyword("or") || *self.peek() == Tok::Op("||") {
+            self.next();
+            lhs = Expr::Bin(BinOp::Or, Box::new(lhs), Box::new(self.and()?));
+        }
+        Ok(lhs)
+    }
+
+    fn and(&mut self) -> Result<Expr, ParseError> {
+        let mut lhs = self.not()?;
+        while self.at_keyword("and") || *self.peek() == Tok::Op("&&") {
+            self.next();
+            lhs = Expr::Bin(BinOp::And, Box::new(lhs), Box::new(self.not()?));
+        }
+        Ok(lhs)
+    }
+
+    fn not(&mut self) -> Result<Expr, ParseError> {
+        if self.at_keyword("not") || *self.peek() == Tok::Op("!") {
+            self.next();
+            return Ok(Expr::Not(Box::new(self.not()?)));
+        }
+        self.compare()
+    }
+
+    /// At most one comparison: `a < b < c` reads as a mistake, not as
+    /// `(a < b) < c`.
+    fn compare(&mut self) -> Result<Expr, ParseError> {
+        let lhs = self.sum()?;
+        let Some(op) = self.compare_op() else {
+            return Ok(lhs);
+        };
+        self.next();
+        let rhs = self.sum()?;
+        if self.compare_op().is_some() {
+            return Err(self.error("comparisons can't be chained; join them with 'and'"));
+        }
+        Ok(Expr::Bin(op, Box::new(lhs), Box::new(rhs)))
+    }
+
+    fn compare_op(&self) -> Option<BinOp> {
+        match self.peek() {
+            Tok::Op("<") => Some(BinOp::Lt),
+            Tok::Op("<=") => Some(BinOp::Le),
+            Tok::Op(">") => Some(BinOp::Gt),
+            Tok::Op(">=") => Some(BinOp::Ge),
+            Tok::Op("==") => Some(BinOp::Eq),
+            Tok::Op("!=") => Some(BinOp::Ne),
+            _ => None,
+        }
+    }
+
+    fn sum(&mut self) -> Result<Expr, ParseError> {
+        let mut lhs = self.term()?;
+        loop {
+            let op = match self.peek() {
+                Tok::Op("+") => BinOp::Add,
+                Tok::Op("-") => BinOp::Sub,
+                _ => return Ok(lhs),
+            };
+            self.next();
+            lhs = Expr::Bin(op, Box::new(lhs), Box::new(self.term()?));
+        }
+    }
+
+    fn term(&mut self) -> Result<Expr, ParseError> {
+        let mut lhs = self.unary()?;
+        loop {
+            let op = match self.peek() {
+                Tok::Op("*") => BinOp::Mul,
+                Tok::Op("/") => BinOp::Div,
+                _ => return Ok(lhs),
+            };
+            self.next();
+            lhs = Expr::Bin(op, Box::new(lhs), Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, ParseError> {
+        if *self.peek() == Tok::Op("-") {
+            self.next();
+            return Ok(Expr::Neg(Box::new(self.unary()?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr, ParseError> {
+        let pos = self.pos();
+        match self.next() {
+            Tok::Num(n) => Ok(Expr::Num(n)),
+            Tok::Str(s) => Ok(Expr::Var(s)),
+            Tok::Name(name) if *self.peek() == Tok::LParen => self.call(&name, pos),
+            Tok::Name(name) => Ok(Expr::Var(name)),
+            Tok::LParen => {
+                let e = self.expr()?;
+                self.expect(Tok::RParen)?;
+                Ok(e)
+            }
+            t => Err(ParseError::new(
+                pos,
+                format!("expected a value, found {}", t.describe()),
+            )),
+        }
+    }
+
+    /// `name(…)`, with the name already consumed.
+    fn call(&mut self, name: &str, pos: usize) -> Result<Expr, ParseError> {
+        self.expect(Tok::LParen)?;
+        if name.eq_ignore_ascii_case("var") {
+            let Tok::Str(id) = self.next() else {
+                return Err(ParseError::new(pos, "var() takes a counter ID in quotes"));
+            };
+            self.expect(Tok::RParen)?;
+            return Ok(Expr::Var(id));
+        }
+        if let Some(func) = ClockFn::parse(name) {
+            let Tok::Str(clock) = self.next() else {
+                let reason = format!("{}() takes a name in quotes", name.to_ascii_lowercase());
+                return Err(ParseError::new(pos, reason));
+            };
+            self.expect(Tok::RParen)?;
+            return Ok(Expr::Clock(func, clock));
+        }
+        let func = Func::parse(name)
+            .ok_or_else(|| ParseError::new(pos, format!("unknown function '{name}'")))?;
+
+        let mut args = Vec::new();
+        if *self.peek() != Tok::RParen {
+            args.push(self.expr()?);
+            while *self.peek() == Tok::Comma {
+                self.next();
+                args.push(self.expr()?);
+            }
+        }
+        self.expect(Tok::RParen)?;
+
+        let (min, max) = func.arity();
+        if !(min..=max).contains(&args.len()) {
+            let wanted = match (min, max) {
+                (n, m) if n == m => format!("{n}"),
+                (n, usize::MAX) => format!("at least {n}"),
+                (n, m) => format!("{n} or {m}"),
+            };
+            let plural = if wanted == "1" { "" } else { "s" };
+            let reason = format!(
+                "{}() takes {wanted} argument{plural}, got {}",
+                name.to_ascii_lowercase(),
+                args.len()
+            );
+            return Err(ParseError::new(pos, reason));
+        }
+        Ok(Expr::Call(func, args))
+    }
+}
+
+// ── Evaluation ───────────────────────────────────────────────────────────────
+
+impl Expr {
+    /// Evaluate with `env` giving each referenced value. Division by zero
+    /// is 0. [`Arith::Integer`] gives a [`Number::Int`] and
+    /// [`Arith::Decimal`] a [`Number::Dec`].
+    pub fn eval(&self, arith: Arith, env: &impl Env) -> Number {
+        match arith {
+            Arith::Integer => Number::Int(self.eval_int(env)),
+            Arith::Decimal => Number::Dec(self.eval_dec(env)),
+        }
+    }
+
+    /// Exact, like counters: every operation saturates, `/` truncates, and
+    /// a fraction is dropped (or, from a decimal result, rounded) on read.
+    fn eval_int(&self, env: &impl Env) -> i64 {
+        let truth = |b: bool| i64::from(b);
+        match self {
+            Expr::Num(Number::Int(n)) => *n,
+            Expr::Num(Number::Dec(x)) => x.trunc() as i64,
+            Expr::Var(id) => env.var(id).whole(),
+            Expr::Clock(func, name) => env.clock(*func, name),
+            Expr::Neg(e) => e.eval_int(env).saturating_neg(),
+            Expr::Not(e) => truth(e.eval_int(env) == 0),
+            Expr::Bin(op, a, b) => {
+                let (a, b) = (a.eval_int(env), b.eval_int(env));
+                match op {
+                    BinOp::Add => a.saturating_add(b),
+                    BinOp::Sub => a.saturating_sub(b),
+                    BinOp::Mul => a.saturating_mul(b),
+                    BinOp::Div if b == 0 => 0,
+                    BinOp::Div => a.saturating_div(b),
+                    BinOp::Lt => truth(a < b),
+                    BinOp::Le => truth(a <= b),
+                    BinOp::Gt => truth(a > b),
+                    BinOp::Ge => truth(a >= b),
+                    BinOp::Eq => truth(a == b),
+                    BinOp::Ne => truth(a != b),
+                    BinOp::And => truth(a != 0 && b != 0),
+                    BinOp::Or => truth(a != 0 || b != 0),
+                }
+            }
+            Expr::Call(Func::If, args) => {
+                let branch = if args[0].eval_int(env) != 0 { 1 } else { 2 };
+                args[branch].eval_int(env)
+            }
+            Expr::Call(func, args) => {
+                let v: Vec<i64> = args.iter().map(|a| a.eval_int(env)).collect();
+                match func {
+                    Func::Min => v.into_iter().fold(i64::MAX, i64::min),
+                    Func::Max => v.into_iter().fold(i64::MIN, i64::max),
+                    Func::Abs => v[0].saturating_abs(),
+                    Func::Sign => v[0].signum(),
+                    Func::Clamp => v[0].max(v[1]).min(v[2]),
+                    Func::Round => round_whole(v[0], v.get(1).copied().unwrap_or(0)),
+                    Func::If => unreachable!("handled above"),
+                }
+            }
+        }
+    }
+
+    fn eval_dec(&self, env: &impl Env) -> f64 {
+        let truth = |b: bool| if b { 1.0 } else { 0.0 };
+        match self {
+            Expr::Num(n) => n.to_f64(),
+            Expr::Var(id) => env.var(id).to_f64(),
+            Expr::Clock(func, name) => env.clock(*func, name) as f64,
+            Expr::Neg(e) => -e.eval_dec(env),
+            Expr::Not(e) => truth(e.eval_dec(env) == 0.0),
+            Expr::Bin(op, a, b) => {
+                let (a, b) = (a.eval_dec(env), b.eval_dec(env));
+                match op {
+                    BinOp::Add => a + b,
+                    BinOp::Sub => a - b,
+                    BinOp::Mul => a * b,
+                    BinOp::Div if b == 0.0 => 0.0,
+                    BinOp::Div => a / b,
+                    BinOp::Lt => truth(a < b),
+                    BinOp::Le => truth(a <= b),
+                    BinOp::Gt => truth(a > b),
+                    BinOp::Ge => truth(a >= b),
+                    BinOp::Eq => truth(a == b),
+                    BinOp::Ne => truth(a != b),
+                    BinOp::And => truth(a != 0.0 && b != 0.0),
+                    BinOp::Or => truth(a != 0.0 || b != 0.0),
+                }
+            }
+            Expr::Call(Func::If, args) => {
+                let branch = if args[0].eval_dec(env) != 0.0 { 1 } else { 2 };
+                args[branch].eval_dec(env)
+            }
+            Expr::Call(func, args) => {
+                let v: Vec<f64> = args.iter().map(|a| a.eval_dec(env)).collect();
+                match func {
+                    Func::Min => v.into_iter().fold(f64::INFINITY, f64::min),
+                    Func::Max => v.into_iter().fold(f64::NEG_INFINITY, f64::max),
+                    Func::Abs => v[0].abs(),
+                    Func::Sign => truth(v[0] > 0.0) - truth(v[0] < 0.0),
+                    Func::Clamp => v[0].max(v[1]).min(v[2]),
+                    Func::Round => round_places(v[0], v.get(1).copied().unwrap_or(0.0)),
+                    Func::If => unreachable!("handled above"),
+                }
+            }
+        }
+    }
+
+    /// Every ID the expression references.
+    pub fn vars(&self) -> Vec<&str> {
+        let mut out = Vec::new();
+        self.walk(&mut |e| {
+            if let Expr::Var(id) = e {
+                out.push(id.as_str());
+            }
+        });
+        out
+    }
+
+    /// Every timer or stopwatch name the expression reads.
+    pub fn clocks(&self) -> Vec<&str> {
+        let mut out = Vec::new();
+        self.walk(&mut |e| {
+            if let Expr::Clock(_, name) = e {
+                out.push(name.as_str());
+            }
+        });
+        out
+    }
+
+    fn walk<'a>(&'a self, f: &mut impl FnMut(&'a Expr)) {
+        f(self);
+        match self {
+            Expr::Num(_) | Expr::Var(_) | Expr::Clock(..) => {}
+            Expr::Neg(e) | Expr::Not(e) => e.walk(f),
+            Expr::Bin(_, a, b) => {
+                a.walk(f);
+                b.walk(f);
+            }
+            Expr::Call(_, args) => args.iter().for_each(|a| a.walk(f)),
+        }
+    }
+}
+
+/// `round(x, places)`: to `places` decimals, or with negative places to
+/// tens, hundreds, … Halves round away from zero.
+fn round_places(x: f64, places: f64) -> f64 {
+    // Beyond this an f64 has no digits left to round.
+    let places = places.trunc().clamp(-18.0, 18.0) as i32;
+    let unit = 10f64.powi(places);
+    (x * unit).round() / unit
+}
+
+/// `round(n, places)` on a whole number: only negative places (tens,
+/// hundreds, …) change it. Halves round away from zero, saturating.
+fn round_whole(n: i64, places: i64) -> i64 {
+    if places >= 0 {
+        return n;
+    }
+    // 10^18 is the largest power of ten an i64 holds.
+    let unit = 10i64.pow(places.unsigned_abs().min(18) as u32);
+    let rem = n % unit;
+    let down = n - rem;
+    if rem.unsigned_abs() * 2 >= unit.unsigned_abs() {
+        down.saturating_add(unit * n.signum())
+    } else {
+        down
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Arith, ClockFn, Env, Number, ParseError, parse};
+
+    const VARS: [(&str, Number); 6] = [
+        ("A", Number::Int(7)),
+        ("B", Number::Int(2)),
+        ("my counter", Number::Int(10)),
+        ("deaths.high", Number::Int(4)),
+        // Past 2^53, where an f64 can't tell it from its neighbours.
+        ("big", Number::Int(9_007_199_254_740_993)),
+        ("kd", Number::Dec(2.5)),
+    ];
+
+    struct TestEnv;
+
+    impl Env for TestEnv {
+        fn var(&self, id: &str) -> Number {
+            VARS.iter()
+                .find(|v| v.0 == id)
+                .map_or(Number::Int(0), |v| v.1)
+        }
+
+        fn clock(&self, func: ClockFn, name: &str) -> i64 {
+            match (func, name) {
+                (ClockFn::Remaining, "round") => 90,
+                (ClockFn::Elapsed, "match") => 150,
+                (ClockFn::Running, "match") => 1,
+                _ => 0,
+            }
+        }
+    }
+
+    fn eval_in(arith: Arith, expr: &str) -> Number {
+        parse(expr).unwrap().eval(arith, &TestEnv)
+    }
+
+    fn eval(expr: &str) -> i64 {
+        match eval_in(Arith::Integer, expr) {
+            Number::Int(n) => n,
+            other => panic!("integer mode gave {other:?}"),
+        }
+    }
+
+    fn dec(expr: &str) -> f64 {
+        match eval_in(Arith::Decimal, expr) {
+            Number::Dec(x) => x,
+            other => panic!("decimal mode gave {other:?}"),
+        }
+    }
+
+    fn err(expr: &str) -> ParseError {
+        parse(expr).unwrap_err()
+    }
+
+    #[test]
+    fn precedence_and_grouping() {
+        assert_eq!(eval("1 + 2 * 3"), 7);
+        assert_eq!(eval("(1 + 2) * 3"), 9);
+        assert_eq!(eval("A - B - 1"), 4);
+        assert_eq!(eval("-A * -B"), 14);
+        assert_eq!(eval("A / B"), 3);
+        assert_eq!(eval("A / 0"), 0);
+    }
+
+    #[test]
+    fn names_strings_and_var_reference_counters() {
+        assert_eq!(eval("\"my counter\" + var('A')"), 17);
+        assert_eq!(eval("`my counter` - deaths.high"), 6);
+    }
+
+    #[test]
+    fn errors_point_at_the_problem() {
+        assert_eq!(err("A + $").pos, 4);
+        assert_eq!(
+            err("(A + B").reason,
+            "expected ')', found end of expression"
+        );
+        assert_eq!(err("A + B)").reason, "unmatched ')'");
+        assert_eq!(err("A +").pos, 3);
+        assert_eq!(err("A B").reason, "expected an operator, found name 'B'");
+        assert_eq!(err("\"open").reason, "unterminated string");
+        assert_eq!(err("foo(1)").reason, "unknown function 'foo'");
+    }
+
+    #[test]
+    fn comparisons_and_logic_give_zero_or_one() {
+        assert_eq!(eval("A > B"), 1);
+        assert_eq!(eval("A <= B"), 0);
+        assert_eq!(eval("A == 7 and B != 7"), 1);
+        assert_eq!(eval("A < B or B < 1"), 0);
+        assert_eq!(eval("not A"), 0);
+        assert_eq!(eval("!0 && 3"), 1);
+    }
+
+    #[test]
+    fn logic_binds_looser_than_comparison_and_arithmetic() {
+        // `or` below `and` below `not` below comparisons below `+`.
+        assert_eq!(eval("1 or 0 and 0"), 1);
+        assert_eq!(eval("not 1 == 2"), 1);
+        assert_eq!(eval("A - B > 4"), 1);
+        assert_eq!(eval("(A > B) + (A > 0)"), 2);
+    }
+
+    #[test]
+    fn functions() {
+        assert_eq!(eval("min(A, B, 5)"), 2);
+        assert_eq!(eval("max(A, B) * 2"), 14);
+        assert_eq!(eval("abs(B - A)"), 5);
+        assert_eq!(eval("clamp(A, 0, 5)"), 5);
+        assert_eq!(eval("clamp(-A, 0, 5)"), 0);
+        assert_eq!(eval("sign(B - A)"), -1);
+        assert_eq!(eval("round(A)"), 7);
+        assert_eq!(eval("round(1250, -2)"), 1300);
+        assert_eq!(eval("round(-1250, -2)"), -1300);
+        assert_eq!(eval("round(1249, -2)"), 1200);
+        assert_eq!(eval("if(A > B, A - B, 0)"), 5);
+        assert_eq!(eval("if(B > A, B - A, 0)"), 0);
+        assert_eq!(eval("MAX(1, 2)"), 2);
+    }
+
+    #[test]
+    fn calls_bind_like_a_single_value() {
+        assert_eq!(eval("-abs(A) * 2"), -14);
+        assert_eq!(eval("1 + if(0, 1, 2) * 3"), 7);
+        assert_eq!(eval("max(A, B) - min(A, B) * 2"), 3);
+        assert_eq!(eval("sign(A) + -sign(-B)"), 2);
+    }
+
+    #[test]
+    fn function_arity_is_checked() {
+        assert_eq!(
+            err("min(A)").reason,
+            "min() takes at least 2 arguments, got 1"
+        );
+        assert_eq!(err("abs(A, B)").reason, "abs() takes 1 argument, got 2");
+        assert_eq!(
+            err("round()").reason,
+            "round() takes 1 or 2 arguments, got 0"
+        );
+        assert_eq!(err("A + if(A, B)").reason, "if() takes 3 arguments, got 2");
+        assert_eq!(err("A + if(A, B)").pos, 4);
+        assert_eq!(
+            err("clamp(A, B)").reason,
+            "clamp() takes 3 arguments, got 2"
+        );
+        assert_eq!(err("sign()").reason, "sign() takes 1 argument, got 0");
+        assert_eq!(
+            err("max(1)").reason,
+            "max() takes at least 2 arguments, got 1"
+        );
+        assert_eq!(err("max(A,)").reason, "expected a value, found ')'");
+    }
+
+    #[test]
+    fn comparison_mistakes_are_errors() {
+        let chained = "comparisons can't be chained; join them with 'and'";
+        assert_eq!(err("1 < A < 9").reason, chained);
+        assert_eq!(err("A = B").reason, "use '==' to compare");
+        assert_eq!(err("A & B").reason, "unexpected '&'");
+    }
+
+    #[test]
+    fn decimal_mode_keeps_fractions() {
+        assert_eq!(dec("A / B"), 3.5);
+        assert_eq!(dec("A / 0"), 0.0);
+        assert_eq!(dec("A * 0.5 + 0.25"), 3.75);
+        assert_eq!(dec("round(A / 3, 2)"), 2.33);
+        assert_eq!(dec("kd * 2"), 5.0);
+        // Integer mode truncates each division and each fraction it reads,
+        // except a decimal result, which it sees as its rounded value.
+        assert_eq!(eval("A / B * B"), 6);
+        assert_eq!(eval("A * 0.5"), 0);
+        assert_eq!(eval("-A / B"), -3);
+        assert_eq!(eval("kd * 2"), 6);
+    }
+
+    #[test]
+    fn integer_mode_is_exact_and_saturates() {
+        assert_eq!(eval("big + 0"), 9_007_199_254_740_993);
+        assert_eq!(eval("big - 1 - big"), -1);
+        assert_eq!(eval("9007199254740993 * 1"), 9_007_199_254_740_993);
+        assert_eq!(eval("big * big"), i64::MAX);
+        assert_eq!(eval("-big * big - big"), i64::MIN);
+        assert_eq!(eval("99999999999999999999"), i64::MAX);
+        assert_eq!(eval("(-big * big) / -1"), i64::MAX);
+        assert_eq!(eval("abs(-big * big)"), i64::MAX);
+        assert_eq!(eval("round(big, -15)"), 9_000_000_000_000_000);
+        assert_eq!(eval("round(9223372036854775807, -1)"), i64::MAX);
+        assert_eq!(eval("round(big, 2)"), 9_007_199_254_740_993);
+    }
+
+    #[test]
+    fn decimal_literals_need_digits_after_the_dot() {
+        assert_eq!(dec("1.5 * 4"), 6.0);
+        assert_eq!(err("1. + A").reason, "expected digits after '.'");
+        assert_eq!(err("1. + A").pos, 2);
+        assert_eq!(err(".5").reason, "unexpected '.'");
+    }
+
+    #[test]
+    fn clock_functions_read_named_clocks() {
+        assert_eq!(eval("remaining(\"round\") / 60"), 1);
+        assert_eq!(dec("A / (elapsed('match') / 60)"), 2.8);
+        assert_eq!(eval("running('match') and not running('round')"), 1);
+        assert_eq!(
+            err("elapsed(match)").reason,
+            "elapsed() takes a name in quotes"
+        );
+        let e = parse("kills / elapsed('match') + remaining('round')").unwrap();
+        assert_eq!(e.clocks(), ["match", "round"]);
+        assert_eq!(e.vars(), ["kills"]);
+    }
+
+    #[test]
+    fn vars_lists_every_reference() {
+        let e = parse("A + var(\"x y\") * (B - A)").unwrap();
+        assert_eq!(e.vars(), ["A", "x y", "B", "A"]);
+    }
+}
@@ -5,6 +5,8 @@
 mod actions;
 mod adapters;
 mod audio;
+mod expr;
+mod paths;
 mod render;
 mod state;
 mod topics;
@@ -14,10 +16,21 @@ use streamdeck_lib::prelude::*;
 use tracing::info;
 
 use actions::{
-    computed::ComputedAction, counter::CounterAction, stopwatch::StopwatchAction,
-    timer::TimerAction, timer_adjust::TimerAdjustAction,
+    computed::ComputedAction,
+    counter::CounterAction,
+    group::GroupControlAction,
+    history::{RedoAction, UndoAction},
+    rate::RateAction,
+    stats::StatsAction,
+    stopwatch::StopwatchAction,
+    timer::TimerAction,
+    timer_adjust::TimerAdjustAction,
+};
+use adapters::{
+    changelog::ChangeLogAdapter, computed::ComputedAdapter, goal::GoalAdapter, rate::RateAdapter,
+    schedule::ScheduleAdapter, stats::StatsAdapter, stopwatch::StopwatchAdapter,
+    timer::TimerAdapter, trigger::TriggerAdapter,
 };
-use adapters::{stopwatch::StopwatchAdapter, timer::TimerAdapter};
 
 pub const PLUGIN_ID: &str = "icu.veelume.counter";
 
@@ -41,8 +54,22 @@ fn main() -> anyhow::Result<()> {
         .add_action(ActionFactory::default_of::<TimerAction>())
         .add_action(ActionFactory::default_of::<TimerAdjustAction>())
         .add_action(ActionFactory::default_of::<StopwatchAction>())
+        .add_action(ActionFactory::default_of::<UndoAction>())
+        .add_action(ActionFactory::default_of::<RedoAction>())
+        .add_action(ActionFactory::default_of::<GroupControlAction>())
+        .add_action(ActionFactory::default_of::<RateAction>())
+        .add_action(ActionFactory::default_of::<StatsAction>())
         .add_adapter(TimerAdapter)
-        .add_adapter(StopwatchAdapter);
+        .add_adapter(StopwatchAdapter)
+        .add_adapter(GoalAdapter)
+        .add_adapter(TriggerAdapter)
+        .add_adapter(ChangeLogAdapter)
+        .add_adapter(ScheduleAdapter)
+        .add_adapter(RateAdapter)
+        .add_adapter(StatsAdapter)
+        .add_adapter(ComputedAdapter);
 
-    run_plugin(plugin)
+    let result = run_plugin(plugin);
+    state::store::flush();
+    result
 }
@@ -0,0 +1,29 @@
+//! Where the plugin keeps files of its own (change logs, exports), outside
+//! the `.sdPlugin` folder so they survive plugin updates.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use directories::ProjectDirs;
+
+/// The plugin's per-user data directory, e.g.
+/// `%APPDATA%\veelume\counter\data` on Windows. `None` if the OS doesn't
+/// report a home directory. Not created here — callers create it on first
+/// write.
+pub fn data_dir() -> Option<PathBuf> {
+    ProjectDirs::from("icu", "veelume", "counter").map(|d| d.data_dir().to_path_buf())
+}
+
+/// Write `text` to `path` via a temp file beside it and a rename, so a crash
+/// mid-write leaves the previous version intact. Creates the parent directory.
+pub fn write_atomic(path: &Path, text: &str) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    fs::write(&tmp, text)?;
+    fs::rename(&tmp, path)
+}
@@ -1,11 +1,14 @@
 use std::sync::OnceLock;
 
+use serde_json::{Map, Value, json};
 use streamdeck_lib::Context;
 use streamdeck_render::{
     BorderStyle, Canvas, Color, FontHandle, FontRegistry, HAlign, TextOptions, VAlign, WrapOptions,
     measure_line, wrap_text,
 };
 
+use crate::state::goal::GoalStyle;
+
 // ── Palette ────────────────────────────────────────────────────────────────
 /// Positive (add) adjustment — green.
 const ADD_COLOR: Color = Color::rgb(64, 200, 110);
@@ -13,8 +16,22 @@ const ADD_COLOR: Color = Color::rgb(64, 200, 110);
 const SUB_COLOR: Color = Color::rgb(231, 90, 76);
 /// Background fill for the expired ("DONE") timer state — unmistakable red.
 const DONE_BG: Color = Color::rgb(184, 50, 40);
+/// Background fill for a key that can't show anything ("ERR") — amber, so it
+/// isn't mistaken for an expired timer.
+const ERROR_BG: Color = Color::rgb(170, 96, 20);
 /// Secondary label (the timer name) — dimmed white, readable on dark or red.
 const LABEL_COLOR: Color = Color::rgba(255, 255, 255, 190);
+/// A command button with nothing to act on (e.g. empty undo history).
+const DISABLED_COLOR: Color = Color::rgba(255, 255, 255, 80);
+/// Goal progress fill — blue, distinct from the add/subtract greens and reds.
+const PROGRESS_COLOR: Color = Color::rgb(80, 160, 255);
+/// The unfilled part of a progress bar/ring.
+const TRACK_COLOR: Color = Color::rgba(255, 255, 255, 45);
+/// Background fill once a goal is reached — a calm, unmistakable green.
+const GOAL_BG: Color = Color::rgb(28, 122, 70);
+
+/// Most lines a counter's cycle label may wrap onto.
+pub const LABEL_MAX_LINES: usize = 3;
 
 /// Transient action feedback drawn as an **edge vignette** — visible at the
 /// corners around a finger, unlike the native `showOk`/`showAlert` which draw
@@ -61,20 +78,264 @@ fn font() -> &'static FontHandle {
     })
 }
 
-/// Render an integer value onto a Stream Deck button (144×144 PNG).
+/// Render an integer value onto a Stream Deck button (144×144 PNG), formatted
+/// per `fmt`.
 ///
 /// The font size scales down automatically for long numbers so they always fit.
-pub fn render_number(cx: &Context, ctx_id: &str, value: i64) {
-    let text = value.to_string();
-    render_centered_text(cx, ctx_id, &text, None);
+pub fn render_number(cx: &Context, ctx_id: &str, value: i64, fmt: &NumberFormat) {
+    let text = fmt.format(value);
+    render_centered_text(cx, ctx_id, &text, 1, Color::TRANSPARENT, None);
 }
 
-/// Render a counter value with a transient [`Feedback`] vignette overlaid —
-/// used to confirm a press took effect even while a finger covers the center.
-/// The caller is responsible for re-rendering the plain value afterwards.
-pub fn render_number_feedback(cx: &Context, ctx_id: &str, value: i64, fb: Feedback) {
-    let text = value.to_string();
-    render_centered_text(cx, ctx_id, &text, Some(fb));
+/// Render a fractional value, rounded and formatted per `dec` and `fmt`.
+pub fn render_decimal(
+    cx: &Context,
+    ctx_id: &str,
+    value: f64,
+    fmt: &NumberFormat,
+    dec: &DecimalFormat,
+) {
+    let text = fmt.format_decimal(value, dec);
+    render_centered_text(cx, ctx_id, &text, 1, Color::TRANSPARENT, None);
+}
+
+// ── Number formatting ──────────────────────────────────────────────────────
+
+/// How digits are written.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NumberBase {
+    #[default]
+    Decimal,
+    /// Uppercase hexadecimal, e.g. `FF`.
+    Hex,
+    Binary,
+    /// Roman numerals for 1..=3999 (`N` for zero); anything else falls back
+    /// to decimal since Roman has no notation for it.
+    Roman,
+}
+
+/// Display template for an integer, shared by counter and computed keys. The
+/// default renders the bare number exactly as before.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct NumberFormat {
+    /// Text before the number, e.g. `$`.
+    pub prefix: String,
+    /// Text after the number, e.g. ` pts`.
+    pub suffix: String,
+    pub base: NumberBase,
+    /// Group decimal digits in threes with `,` (decimal only).
+    pub thousands: bool,
+    /// Zero-pad the digits to at least this many (not Roman).
+    pub pad_digits: usize,
+    /// Show `+` on positive values.
+    pub force_sign: bool,
+}
+
+impl NumberFormat {
+    /// Read the format fields shared by the counter and computed PIs.
+    pub fn from_settings(v: &Map<String, Value>) -> Self {
+        let text = |k: &str| {
+            v.get(k)
+                .and_then(|x| x.as_str())
+                .unwrap_or_default()
+                .to_string()
+        };
+        let flag = |k: &str| v.get(k).and_then(|x| x.as_bool()).unwrap_or(false);
+        let pad_digits = match v.get("padDigits") {
+            Some(Value::Number(n)) => n.as_u64().unwrap_or(0),
+            Some(Value::String(s)) => s.trim().parse().unwrap_or(0),
+            _ => 0,
+        };
+        let base = match v.get("numberBase").and_then(|x| x.as_str()) {
+            Some("hex") => NumberBase::Hex,
+            Some("binary") => NumberBase::Binary,
+            Some("roman") => NumberBase::Roman,
+            _ => NumberBase::Decimal,
+        };
+        Self {
+            prefix: text("prefix"),
+            suffix: text("suffix"),
+            base,
+            thousands: flag("thousands"),
+            // More than a key can show anyway; keeps a typo from allocating.
+            pad_digits: pad_digits.min(MAX_PAD_DIGITS) as usize,
+            force_sign: flag("forceSign"),
+        }
+    }
+
+    /// Format `value` as `prefix` + sign + digits + `suffix`.
+    pub fn format(&self, value: i64) -> String {
+        let sign = if value < 0 {
+            "-"
+        } else if value > 0 && self.force_sign {
+            "+"
+        } else {
+            ""
+        };
+        let abs = value.unsigned_abs();
+        let digits = match self.base {
+            NumberBase::Roman if (1..=3999).contains(&abs) => to_roman(abs),
+            NumberBase::Roman if abs == 0 => "N".to_string(),
+            NumberBase::Hex => self.padded(format!("{abs:X}")),
+            NumberBase::Binary => self.padded(format!("{abs:b}")),
+            NumberBase::Decimal | NumberBase::Roman => {
+                let d = self.padded(abs.to_string());
+                if self.thousands { group_thousands(&d) } else { d }
+            }
+        };
+        format!("{}{sign}{digits}{}", self.prefix, self.suffix)
+    }
+
+    /// Format a fractional `value` to `dec.places` decimals. Prefix, suffix,
+    /// sign, padding and thousands apply as for [`Self::format`]; the base
+    /// doesn't, since only decimal has a point.
+    pub fn format_decimal(&self, value: f64, dec: &DecimalFormat) -> String {
+        let value = if dec.percent { value * 100.0 } else { value };
+        let unit = 10u128.pow(dec.places);
+        let scaled = dec.rounding.apply(value * unit as f64);
+        // Signed after rounding, so -0.001 shows as 0.00 rather than -0.00.
+        let sign = if scaled < 0.0 {
+            "-"
+        } else if scaled > 0.0 && self.force_sign {
+            "+"
+        } else {
+            ""
+        };
+        let abs = scaled.abs() as u128;
+        let mut whole = self.padded((abs / unit).to_string());
+        if self.thousands {
+            whole = group_thousands(&whole);
+        }
+        let places = dec.places as usize;
+        let frac = match places {
+            0 => String::new(),
+            _ => format!(".{:0>places$}", abs % unit),
+        };
+        let percent = if dec.percent { "%" } else { "" };
+        format!("{}{sign}{whole}{frac}{percent}{}", self.prefix, self.suffix)
+    }
+
+    fn padded(&self, digits: String) -> String {
+        format!("{digits:0>width$}", width = self.pad_digits)
+    }
+}
+
+const MAX_PAD_DIGITS: u64 = 20;
+
+/// How a fractional value is cut to its decimal places.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Rounding {
+    /// Halves away from zero: 2.5 → 3, -2.5 → -3.
+    #[default]
+    HalfUp,
+    /// Halves to the even neighbour: 2.5 → 2, 3.5 → 4.
+    HalfEven,
+    /// Toward zero.
+    Down,
+    Floor,
+    Ceil,
+}
+
+impl Rounding {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "halfUp" => Some(Rounding::HalfUp),
+            "halfEven" => Some(Rounding::HalfEven),
+            "down" => Some(Rounding::Down),
+            "floor" => Some(Rounding::Floor),
+            "ceil" => Some(Rounding::Ceil),
+            _ => None,
+        }
+    }
+
+    fn apply(self, x: f64) -> f64 {
+        match self {
+            Rounding::HalfUp => x.round(),
+            Rounding::HalfEven => x.round_ties_even(),
+            Rounding::Down => x.trunc(),
+            Rounding::Floor => x.floor(),
+            Rounding::Ceil => x.ceil(),
+        }
+    }
+}
+
+/// Decimal display for computed keys evaluated with fractions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DecimalFormat {
+    /// Digits after the point, `0..=MAX_DECIMAL_PLACES`.
+    pub places: u32,
+    pub rounding: Rounding,
+    /// Show `0.235` as `23.5%` (with one place).
+    pub percent: bool,
+}
+
+impl Default for DecimalFormat {
+    fn default() -> Self {
+        Self {
+            places: 2,
+            rounding: Rounding::default(),
+            percent: false,
+        }
+    }
+}
+
+impl DecimalFormat {
+    /// Read `decimals`, `rounding` and `percent`.
+    pub fn from_settings(v: &Map<String, Value>) -> Self {
+        let places = match v.get("decimals") {
+            Some(Value::Number(n)) => n.as_u64(),
+            Some(Value::String(s)) => s.trim().parse().ok(),
+            _ => None,
+        };
+        let rounding = v.get("rounding").and_then(|x| x.as_str());
+        Self {
+            places: places.map_or(2, |p| p.min(MAX_DECIMAL_PLACES) as u32),
+            rounding: rounding.and_then(Rounding::parse).unwrap_or_default(),
+            percent: v.get("percent").and_then(|x| x.as_bool()).unwrap_or(false),
+        }
+    }
+}
+
+const MAX_DECIMAL_PLACES: u64 = 6;
+
+/// Insert `,` between every group of three digits, counting from the right.
+fn group_thousands(digits: &str) -> String {
+    let len = digits.len();
+    let mut out = String::with_capacity(len + len / 3);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (len - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Standard subtractive Roman numerals. Caller guarantees `1..=3999`.
+fn to_roman(mut n: u64) -> String {
+    const TABLE: [(u64, &str); 13] = [
+        (1000, "M"),
+        (900, "CM"),
+        (500, "D"),
+        (400, "CD"),
+        (100, "C"),
+        (90, "XC"),
+        (50, "L"),
+        (40, "XL"),
+        (10, "X"),
+        (9, "IX"),
+        (5, "V"),
+        (4, "IV"),
+        (1, "I"),
+    ];
+    let mut out = String::new();
+    for (v, sym) in TABLE {
+        while n >= v {
+            out.push_str(sym);
+            n -= v;
+        }
+    }
+    out
 }
 
 /// All time values are sized against this reference so `01:45`, `05:00`, and
@@ -117,6 +378,13 @@ pub fn render_time(cx: &Context, ctx_id: &str, total_secs: u64, name: &str, runn
     );
 }
 
+/// Show a timer on the dial touch strip: the time left (`None` once expired,
+/// shown as "DONE") over a bar of the fraction of the countdown remaining.
+pub fn render_time_dial(cx: &Context, ctx_id: &str, secs: Option<u64>, name: &str, left: f32) {
+    let text = secs.map_or_else(|| "DONE".to_string(), |s| fmt_duration(s, ':'));
+    render_dial(cx, ctx_id, name, &text, Some(left));
+}
+
 /// Render a "+/-" adjustment button: a signed, color-coded delta (green for
 /// add, red for subtract) over the target timer `name`.
 pub fn render_adjust(cx: &Context, ctx_id: &str, delta_secs: i64, name: &str) {
@@ -128,6 +396,55 @@ pub fn render_adjust(cx: &Context, ctx_id: &str, delta_secs: i64, name: &str) {
     render_labeled(cx, ctx_id, &text, &text, color, name, Color::TRANSPARENT);
 }
 
+/// Format a rate for display, e.g. "12/min" or "0.4/h". One decimal below
+/// 10 so slow rates don't read as a flat 0.
+pub fn fmt_rate(rate: f64, per_secs: u64) -> String {
+    let unit = match per_secs {
+        1 => "s",
+        3600 => "h",
+        _ => "min",
+    };
+    if rate.abs() < 9.95 {
+        // Avoid "-0.0" once the window has drained.
+        let r = if rate.abs() < 0.05 { 0.0 } else { rate };
+        format!("{r:.1}/{unit}")
+    } else {
+        format!("{rate:.0}/{unit}")
+    }
+}
+
+/// Render a rate key: the already formatted rate over the counter ID.
+pub fn render_rate(cx: &Context, ctx_id: &str, text: &str, counter_key: &str) {
+    render_labeled(
+        cx,
+        ctx_id,
+        text,
+        text,
+        Color::WHITE,
+        counter_key,
+        Color::TRANSPARENT,
+    );
+}
+
+/// Render a counter stats key: the formatted statistic over its label.
+pub fn render_stat(cx: &Context, ctx_id: &str, text: &str, label: &str) {
+    let bg = Color::TRANSPARENT;
+    render_labeled(cx, ctx_id, text, text, Color::WHITE, label, bg);
+}
+
+/// Render a command button (e.g. "UNDO") over the counter ID it targets,
+/// dimmed while there is nothing for it to do.
+pub fn render_command(cx: &Context, ctx_id: &str, text: &str, target: &str, enabled: bool) {
+    let color = if enabled { Color::WHITE } else { DISABLED_COLOR };
+    render_labeled(cx, ctx_id, text, text, color, target, Color::TRANSPARENT);
+}
+
+/// Render a computed key that can't be evaluated: "ERR" over a short
+/// `label` (`col 7` for a parse error, `cycle`), on an amber background.
+pub fn render_expr_error(cx: &Context, ctx_id: &str, label: &str) {
+    render_labeled(cx, ctx_id, "ERR", "ERR", Color::WHITE, label, ERROR_BG);
+}
+
 /// Render the timer's "expired" state — a filled red background with "DONE"
 /// so a finished timer is impossible to miss at a glance. The label beneath is
 /// the timer `name` when set, otherwise the `reset_secs` it returns to on reset.
@@ -143,28 +460,42 @@ pub fn render_expired(cx: &Context, ctx_id: &str, name: &str, reset_secs: u64) {
 }
 
 /// Render any short text string centered on a button, with auto-scaling font
-/// size and an optional edge-vignette [`Feedback`] frame.
-fn render_centered_text(cx: &Context, ctx_id: &str, text: &str, frame: Option<Feedback>) {
+/// size, an optional background fill (`Color::TRANSPARENT` for none) and an
+/// optional edge-vignette [`Feedback`] frame.
+///
+/// With `max_lines` above 1, multi-word text (e.g. a cycle label like
+/// "Round 1") may wrap at spaces when that lets it stay larger than a single
+/// shrunken line would.
+fn render_centered_text(
+    cx: &Context,
+    ctx_id: &str,
+    text: &str,
+    max_lines: usize,
+    bg: Color,
+    frame: Option<Feedback>,
+) {
     let font = font();
 
-    // Try font sizes from largest to smallest until the text fits in one line.
+    // Try font sizes from largest to smallest until the text fits.
     let sizes = [56.0_f32, 44.0, 36.0, 28.0, 20.0];
     let max_width = 136.0_f32; // leave a small margin inside 144px
 
     let chosen_size = sizes
         .iter()
         .copied()
-        .find(|&size| {
-            let opts = WrapOptions { max_width, max_lines: 1 };
-            let lines = wrap_text(font, size, text, &opts);
-            lines.len() == 1 && lines[0].width_px <= max_width
-        })
+        .find(|&size| fits_wrapped(font, text, size, max_width, max_lines))
         .unwrap_or(20.0); // fallback: always render at minimum size
 
-    let opts = WrapOptions { max_width, max_lines: 1 };
+    let opts = WrapOptions {
+        max_width,
+        max_lines,
+    };
     let lines = wrap_text(font, chosen_size, text, &opts);
 
     let mut canvas = Canvas::key_icon();
+    if bg.a > 0 {
+        canvas.fill(bg);
+    }
     if !lines.is_empty() {
         canvas
             .draw_text(&lines, &TextOptions::new(font.clone(), chosen_size))
@@ -244,6 +575,237 @@ fn render_labeled(
     }
 }
 
+/// Progress toward a counter's goal, as drawn by [`render_counter`].
+#[derive(Clone, Copy, Debug)]
+pub struct GoalProgress {
+    /// `0.0..=1.0` of the way to the goal.
+    pub fraction: f32,
+    pub style: GoalStyle,
+    pub reached: bool,
+}
+
+/// Render a counter key: the value (already formatted as `text`, or its cycle
+/// label) over an optional RGB background fill, with optional goal progress
+/// and an optional [`Feedback`] vignette overlaid last. Without a goal, `text`
+/// may wrap onto up to `max_lines` lines; with one it stays on a single line.
+///
+/// Goal progress is a bar under the number or a ring of dots around it. Once
+/// reached, the key switches to the green "goal reached" style with a full,
+/// white progress mark — unless `bg` is set, which always wins.
+pub fn render_counter(
+    cx: &Context,
+    ctx_id: &str,
+    text: &str,
+    max_lines: usize,
+    bg: Option<[u8; 3]>,
+    goal: Option<GoalProgress>,
+    frame: Option<Feedback>,
+) {
+    const MAX_WIDTH: f32 = 136.0;
+    /// The ring leaves less room for the number than the bar does.
+    const RING_INNER_WIDTH: f32 = 100.0;
+
+    let bg = bg.map_or(Color::TRANSPARENT, |[r, g, b]| Color::rgb(r, g, b));
+    let Some(goal) = goal else {
+        render_centered_text(cx, ctx_id, text, max_lines, bg, frame);
+        return;
+    };
+
+    let font = font();
+    let mut canvas = Canvas::key_icon();
+    if bg.a > 0 {
+        canvas.fill(bg);
+    } else if goal.reached {
+        canvas.fill(GOAL_BG);
+    }
+    let fill = if goal.reached { Color::WHITE } else { PROGRESS_COLOR };
+    let progress = goal.fraction;
+
+    match goal.style {
+        GoalStyle::Bar => {
+            let size = fit_size(font, text, MAX_WIDTH, &[52.0, 44.0, 36.0, 28.0, 20.0]);
+            draw_line(
+                &mut canvas,
+                font,
+                text,
+                size,
+                Color::WHITE,
+                VAlign::Baseline(84.0),
+            );
+            draw_progress_bar(&mut canvas, progress, fill);
+        }
+        GoalStyle::Ring => {
+            let size = fit_size(font, text, RING_INNER_WIDTH, &[44.0, 36.0, 28.0, 20.0]);
+            draw_line(&mut canvas, font, text, size, Color::WHITE, VAlign::Center);
+            draw_progress_ring(&mut canvas, progress, fill);
+        }
+    }
+
+    if let Some(fb) = frame {
+        canvas.draw_border(&fb.border());
+    }
+    if let Ok(data_url) = canvas.finish().to_data_url() {
+        cx.sd().set_image(ctx_id, Some(data_url), None, None);
+    }
+}
+
+/// A rounded-off horizontal bar near the bottom edge, filled left to right.
+fn draw_progress_bar(canvas: &mut Canvas, progress: f32, fill: Color) {
+    const X: f32 = 16.0;
+    const Y: f32 = 108.0;
+    const W: f32 = 112.0;
+    const H: f32 = 12.0;
+    canvas.fill_rect(X, Y, W, H, TRACK_COLOR);
+    let filled = W * progress.clamp(0.0, 1.0);
+    if filled > 0.0 {
+        canvas.fill_rect(X, Y, filled, H, fill);
+    }
+}
+
+/// A ring of dots just inside the key edge, filled clockwise from the top.
+fn draw_progress_ring(canvas: &mut Canvas, progress: f32, fill: Color) {
+    const DOTS: usize = 36;
+    const CENTER: f32 = 72.0;
+    const RADIUS: f32 = 60.0;
+    const DOT_R: f32 = 4.0;
+    let lit = (progress.clamp(0.0, 1.0) * DOTS as f32).round() as usize;
+    for i in 0..DOTS {
+        let angle = -std::f32::consts::FRAC_PI_2 + i as f32 * std::f32::consts::TAU / DOTS as f32;
+        let color = if i < lit { fill } else { TRACK_COLOR };
+        canvas.fill_circle(
+            CENTER + RADIUS * angle.cos(),
+            CENTER + RADIUS * angle.sin(),
+            DOT_R,
+            color,
+        );
+    }
+}
+
+// ── Marks ──────────────────────────────────────────────────────────────────
+
+/// How a counter key draws a small value instead of its digits.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CountStyle {
+    #[default]
+    Digits,
+    /// Tally marks, struck through in groups of five.
+    Tally,
+    /// `limit` pips, the first `value` of them filled.
+    Pips,
+    /// One dot per unit.
+    Dots,
+}
+
+/// Most marks a key has room for; a higher limit is capped to this.
+pub const MAX_MARKS: i64 = 30;
+
+impl CountStyle {
+    /// Whether `value` is drawn as marks rather than digits: it must be
+    /// within `0..=limit` (capped at [`MAX_MARKS`]), and above 0 unless
+    /// there are empty pips to show.
+    pub fn draws(self, value: i64, limit: i64) -> bool {
+        let min = if self == CountStyle::Pips { 0 } else { 1 };
+        self != CountStyle::Digits && (min..=limit.min(MAX_MARKS)).contains(&value)
+    }
+}
+
+/// Render a counter key as marks (see [`CountStyle`]) over an optional RGB
+/// background fill, with an optional [`Feedback`] vignette. Call only when
+/// [`CountStyle::draws`] says so.
+pub fn render_marks(
+    cx: &Context,
+    ctx_id: &str,
+    style: CountStyle,
+    value: i64,
+    limit: i64,
+    bg: Option<[u8; 3]>,
+    frame: Option<Feedback>,
+) {
+    let mut canvas = Canvas::key_icon();
+    if let Some([r, g, b]) = bg {
+        canvas.fill(Color::rgb(r, g, b));
+    }
+    let value = value.clamp(0, MAX_MARKS) as usize;
+    match style {
+        CountStyle::Digits => {}
+        CountStyle::Tally => draw_tally(&mut canvas, value),
+        CountStyle::Pips => draw_dot_grid(&mut canvas, value, limit.clamp(1, MAX_MARKS) as usize),
+        CountStyle::Dots => draw_dot_grid(&mut canvas, value, value),
+    }
+    if let Some(fb) = frame {
+        canvas.draw_border(&fb.border());
+    }
+    if let Ok(data_url) = canvas.finish().to_data_url() {
+        cx.sd().set_image(ctx_id, Some(data_url), None, None);
+    }
+}
+
+/// Columns and rows for `n` marks: a single row up to five, then the
+/// squarest grid at least five wide.
+fn mark_grid(n: usize) -> (usize, usize) {
+    if n <= 5 {
+        return (n.max(1), 1);
+    }
+    let root = n.isqrt();
+    let cols = if root * root < n { root + 1 } else { root }.max(5);
+    (cols, n.div_ceil(cols))
+}
+
+/// `total` dots in a centered grid, the first `filled` of them white and the
+/// rest as empty track.
+fn draw_dot_grid(canvas: &mut Canvas, filled: usize, total: usize) {
+    const AREA: f32 = 120.0;
+    const CENTER: f32 = 72.0;
+    let (cols, rows) = mark_grid(total);
+    let cell = AREA / cols.max(rows) as f32;
+    let r = (cell * 0.36).min(14.0);
+    let left = CENTER - cell * cols as f32 / 2.0;
+    let top = CENTER - cell * rows as f32 / 2.0;
+    for i in 0..total {
+        let x = left + cell * ((i % cols) as f32 + 0.5);
+        let y = top + cell * ((i / cols) as f32 + 0.5);
+        let lit = i < filled;
+        canvas.fill_circle(x, y, r, if lit { Color::WHITE } else { TRACK_COLOR });
+    }
+}
+
+/// `n` tally marks: groups of four strokes and a diagonal through them,
+/// three groups to a row.
+fn draw_tally(canvas: &mut Canvas, n: usize) {
+    const GROUPS_PER_ROW: usize = 3;
+    const STROKE_W: f32 = 5.0;
+    const STROKE_GAP: f32 = 9.0;
+    const GROUP_W: f32 = 3.0 * STROKE_GAP + STROKE_W;
+    const GROUP_GAP: f32 = 12.0;
+    const MARK_H: f32 = 34.0;
+    const ROW_GAP: f32 = 14.0;
+
+    let groups = n.div_ceil(5);
+    let rows = groups.div_ceil(GROUPS_PER_ROW);
+    let height = rows as f32 * MARK_H + (rows.saturating_sub(1)) as f32 * ROW_GAP;
+    let top = 72.0 - height / 2.0;
+    for g in 0..groups {
+        let in_row = (groups - g / GROUPS_PER_ROW * GROUPS_PER_ROW).min(GROUPS_PER_ROW);
+        let row_w = in_row as f32 * GROUP_W + (in_row - 1) as f32 * GROUP_GAP;
+        let x = 72.0 - row_w / 2.0 + (g % GROUPS_PER_ROW) as f32 * (GROUP_W + GROUP_GAP);
+        let y = top + (g / GROUPS_PER_ROW) as f32 * (MARK_H + ROW_GAP);
+        let marks = (n - g * 5).min(5);
+        for s in 0..marks.min(4) {
+            let sx = x + s as f32 * STROKE_GAP;
+            canvas.fill_rect(sx, y, STROKE_W, MARK_H, Color::WHITE);
+        }
+        if marks == 5 {
+            // No line primitive: stamp dots along the diagonal instead.
+            let (x0, y0, x1, y1) = (x - 4.0, y + MARK_H - 6.0, x + GROUP_W + 4.0, y + 6.0);
+            let steps = (x1 - x0).ceil() as usize;
+            for i in 0..=steps {
+                let t = i as f32 / steps as f32;
+                canvas.fill_circle(x0 + (x1 - x0) * t, y0 + (y1 - y0) * t, 2.8, Color::WHITE);
+            }
+        }
+    }
+}
+
 /// Return whichever of `a` / `b` renders wider (measured at a common scale).
 fn wider<'a>(font: &FontHandle, a: &'a str, b: &'a str) -> &'a str {
     if measure_line(font, 10.0, a) >= measure_line(font, 10.0, b) {
@@ -253,6 +815,28 @@ fn wider<'a>(font: &FontHandle, a: &'a str, b: &'a str) -> &'a str {
     }
 }
 
+/// Whether `text` wraps at `size` onto at most `max_lines` lines that each fit
+/// `max_width` and, stacked, fit the key's height.
+fn fits_wrapped(
+    font: &FontHandle,
+    text: &str,
+    size: f32,
+    max_width: f32,
+    max_lines: usize,
+) -> bool {
+    const LINE_HEIGHT: f32 = 1.2;
+    // Ask for one extra line so overflow shows up as too many lines.
+    let opts = WrapOptions {
+        max_width,
+        max_lines: max_lines + 1,
+    };
+    let lines = wrap_text(font, size, text, &opts);
+    !lines.is_empty()
+        && lines.len() <= max_lines
+        && lines.iter().all(|l| l.width_px <= max_width)
+        && lines.len() as f32 * size * LINE_HEIGHT <= max_width
+}
+
 /// Pick the largest size from `sizes` at which `text` fits within `max_width`,
 /// falling back to the smallest provided size.
 fn fit_size(font: &FontHandle, text: &str, max_width: f32, sizes: &[f32]) -> f32 {
@@ -302,9 +886,28 @@ fn draw_line(
     canvas.draw_text(&lines, &topts).ok();
 }
 
+// ── Touch strip ────────────────────────────────────────────────────────────
+
+/// Fill the dial layout (`layouts/dial.json`): `title` above the large
+/// `value`, with a level bar beneath when `bar` is set (`0.0..=1.0`). Only for
+/// Encoder contexts: keypad ones have no layout, so callers check first.
+pub fn render_dial(cx: &Context, ctx_id: &str, title: &str, value: &str, bar: Option<f32>) {
+    let bar = match bar {
+        Some(f) => json!({ "value": (f.clamp(0.0, 1.0) * 100.0).round(), "opacity": 1 }),
+        None => json!({ "value": 0, "opacity": 0 }),
+    };
+    cx.sd().set_feedback(
+        ctx_id,
+        json!({ "title": title, "value": value, "bar": bar }),
+    );
+}
+
 #[cfg(test)]
 mod tests {
-    use super::fmt_duration;
+    use super::{
+        CountStyle, DecimalFormat, NumberBase, NumberFormat, Rounding, fmt_duration, fmt_rate,
+        mark_grid,
+    };
 
     #[test]
     fn under_an_hour_is_mm_ss() {
@@ -328,4 +931,129 @@ mod tests {
         assert_eq!(fmt_duration(90, ' '), "01 30"); // colon blinked off
         assert_eq!(fmt_duration(5400, ' '), "01 30"); // same in HH:MM mode
     }
+
+    #[test]
+    fn rates_keep_a_decimal_only_when_small() {
+        assert_eq!(fmt_rate(12.4, 60), "12/min");
+        assert_eq!(fmt_rate(2.4, 60), "2.4/min");
+        assert_eq!(fmt_rate(-0.01, 3600), "0.0/h");
+        assert_eq!(fmt_rate(-15.0, 1), "-15/s");
+    }
+
+    #[test]
+    fn default_format_is_the_bare_number() {
+        let f = NumberFormat::default();
+        assert_eq!(f.format(0), "0");
+        assert_eq!(f.format(-42), "-42");
+        assert_eq!(f.format(1234567), "1234567");
+    }
+
+    #[test]
+    fn decimal_options_combine() {
+        let f = NumberFormat {
+            prefix: "$".into(),
+            suffix: " pts".into(),
+            thousands: true,
+            force_sign: true,
+            ..Default::default()
+        };
+        assert_eq!(f.format(1234567), "$+1,234,567 pts");
+        assert_eq!(f.format(-999), "$-999 pts");
+        assert_eq!(f.format(0), "$0 pts");
+
+        let padded = NumberFormat {
+            pad_digits: 4,
+            ..Default::default()
+        };
+        assert_eq!(padded.format(7), "0007");
+        assert_eq!(padded.format(-7), "-0007");
+        assert_eq!(padded.format(123456), "123456");
+    }
+
+    #[test]
+    fn decimals_round_per_mode() {
+        let f = NumberFormat::default();
+        let dec = |places, rounding| DecimalFormat {
+            places,
+            rounding,
+            percent: false,
+        };
+        assert_eq!(f.format_decimal(2.345, &dec(2, Rounding::HalfUp)), "2.35");
+        assert_eq!(f.format_decimal(2.5, &dec(0, Rounding::HalfEven)), "2");
+        assert_eq!(f.format_decimal(3.5, &dec(0, Rounding::HalfEven)), "4");
+        assert_eq!(f.format_decimal(-1.25, &dec(1, Rounding::Down)), "-1.2");
+        assert_eq!(f.format_decimal(-1.25, &dec(1, Rounding::Floor)), "-1.3");
+        assert_eq!(f.format_decimal(1.01, &dec(1, Rounding::Ceil)), "1.1");
+        assert_eq!(f.format_decimal(0.05, &dec(3, Rounding::HalfUp)), "0.050");
+        assert_eq!(f.format_decimal(-0.001, &dec(2, Rounding::HalfUp)), "0.00");
+    }
+
+    #[test]
+    fn decimals_take_the_integer_options() {
+        let f = NumberFormat {
+            prefix: "$".into(),
+            suffix: " avg".into(),
+            thousands: true,
+            force_sign: true,
+            ..Default::default()
+        };
+        let two = DecimalFormat::default();
+        assert_eq!(f.format_decimal(1234.5, &two), "$+1,234.50 avg");
+        let pct = DecimalFormat {
+            places: 1,
+            percent: true,
+            ..two
+        };
+        assert_eq!(f.format_decimal(0.2354, &pct), "$+23.5% avg");
+    }
+
+    #[test]
+    fn alternate_bases() {
+        let hex = NumberFormat {
+            base: NumberBase::Hex,
+            pad_digits: 2,
+            ..Default::default()
+        };
+        assert_eq!(hex.format(255), "FF");
+        assert_eq!(hex.format(10), "0A");
+        let bin = NumberFormat {
+            base: NumberBase::Binary,
+            ..Default::default()
+        };
+        assert_eq!(bin.format(5), "101");
+        assert_eq!(bin.format(-2), "-10");
+    }
+
+    #[test]
+    fn roman_numerals() {
+        let f = NumberFormat {
+            base: NumberBase::Roman,
+            ..Default::default()
+        };
+        assert_eq!(f.format(1994), "MCMXCIV");
+        assert_eq!(f.format(4), "IV");
+        assert_eq!(f.format(0), "N");
+        assert_eq!(f.format(-9), "-IX");
+        assert_eq!(f.format(4000), "4000"); // out of Roman range
+    }
+
+    #[test]
+    fn marks_fill_rows_of_five_then_square_up() {
+        assert_eq!(mark_grid(3), (3, 1));
+        assert_eq!(mark_grid(5), (5, 1));
+        assert_eq!(mark_grid(7), (5, 2));
+        assert_eq!(mark_grid(25), (5, 5));
+        assert_eq!(mark_grid(30), (6, 5));
+    }
+
+    #[test]
+    fn marks_fall_back_to_digits_outside_the_limit() {
+        assert!(CountStyle::Tally.draws(7, 10));
+        assert!(!CountStyle::Tally.draws(11, 10));
+        assert!(!CountStyle::Dots.draws(0, 10));
+        assert!(CountStyle::Pips.draws(0, 10));
+        assert!(!CountStyle::Pips.draws(-1, 10));
+        assert!(!CountStyle::Dots.draws(40, 100)); // capped at MAX_MARKS
+        assert!(!CountStyle::Digits.draws(3, 10));
+    }
 }
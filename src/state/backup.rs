@@ -0,0 +1,246 @@
+//! Export/import of running state — the `counters`, `timers` and
+//! `stopwatches` sections of the state store — as a versioned JSON file, so it
+//! can be carried to another machine.
+//!
+//! Everything here works on a plain store document; the caller runs it inside
+//! `Store::with_mut` and then notifies keys of what changed.
+
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::paths::write_atomic;
+
+/// Bumped when the file layout changes incompatibly. Files from a newer
+/// version are refused rather than half-imported.
+pub const BACKUP_VERSION: u64 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Backup {
+    pub version: u64,
+    #[serde(default)]
+    pub exported_at: String,
+    #[serde(default)]
+    pub counters: Map<String, Value>,
+    #[serde(default)]
+    pub timers: Map<String, Value>,
+    #[serde(default)]
+    pub stopwatches: Map<String, Value>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ImportMode {
+    /// Entries in the file overwrite matching ones; everything else is kept.
+    #[default]
+    Merge,
+    /// The file becomes the whole state. Counters missing from it are reset
+    /// to their initial value; timers and stopwatches missing from it are dropped (on-screen
+    /// ones restart from their configured duration / zero).
+    Replace,
+}
+
+/// A counter an import wrote, whether or not its value changed.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ImportedCounter {
+    pub key: String,
+    /// `None` when the counter didn't exist before the import.
+    pub previous: Option<i64>,
+    pub value: i64,
+}
+
+impl ImportedCounter {
+    pub fn changed(&self) -> bool {
+        self.previous != Some(self.value)
+    }
+}
+
+impl Backup {
+    /// Copy the backed-up sections out of the globals map.
+    pub fn snapshot(globals: &Map<String, Value>, exported_at: String) -> Self {
+        let section = |name: &str| {
+            globals
+                .get(name)
+                .and_then(|v| v.as_object())
+                .cloned()
+                .unwrap_or_default()
+        };
+        Self {
+            version: BACKUP_VERSION,
+            exported_at,
+            counters: section("counters"),
+            timers: section("timers"),
+            stopwatches: section("stopwatches"),
+        }
+    }
+
+    /// Parse a backup file's contents, refusing newer versions and counters
+    /// that aren't integers.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let backup: Backup =
+            serde_json::from_str(text).map_err(|e| format!("not a counter backup: {e}"))?;
+        if backup.version > BACKUP_VERSION {
+            return Err(format!(
+                "backup version {} is newer than this plugin supports ({BACKUP_VERSION})",
+                backup.version
+            ));
+        }
+        if let Some((key, _)) = backup.counters.iter().find(|(_, v)| v.as_i64().is_none()) {
+            return Err(format!("counter {key:?} is not a whole number"));
+        }
+        Ok(backup)
+    }
+
+    /// Write the backup into the globals map. Counters `read_only` picks are
+    /// neither written nor reset. Returns every other counter in the file,
+    /// plus those a replace reset, so the caller can publish
+    /// `COUNTER_CHANGED` for each of them.
+    pub fn restore(
+        &self,
+        globals: &mut Map<String, Value>,
+        mode: ImportMode,
+        read_only: impl Fn(&str) -> bool,
+    ) -> Vec<ImportedCounter> {
+        let before: Map<String, Value> = globals
+            .get("counters")
+            .and_then(|v| v.as_object())
+            .cloned()
+            .unwrap_or_default();
+
+        let sections = [
+            ("counters", &self.counters),
+            ("timers", &self.timers),
+            ("stopwatches", &self.stopwatches),
+        ];
+        for (name, incoming) in sections {
+            let section = globals
+                .entry(name.to_string())
+                .or_insert_with(|| Value::Object(Map::new()));
+            if !section.is_object() {
+                *section = Value::Object(Map::new());
+            }
+            let map = section.as_object_mut().unwrap();
+            let writable = |k: &str| name != "counters" || !read_only(k);
+            if mode == ImportMode::Replace {
+                map.retain(|k, _| !writable(k));
+            }
+            let incoming = incoming.iter().filter(|(k, _)| writable(k));
+            map.extend(incoming.map(|(k, v)| (k.clone(), v.clone())));
+        }
+
+        let initials = globals
+            .get("initials")
+            .and_then(|v| v.as_object())
+            .cloned()
+            .unwrap_or_default();
+        let counters = globals["counters"].as_object_mut().unwrap();
+        if mode == ImportMode::Replace {
+            for key in before.keys() {
+                let initial = initials.get(key).and_then(|v| v.as_i64()).unwrap_or(0);
+                counters.entry(key.clone()).or_insert(Value::from(initial));
+            }
+        }
+
+        let mut imported: Vec<ImportedCounter> = counters
+            .iter()
+            .filter(|(key, _)| mode == ImportMode::Replace || self.counters.contains_key(*key))
+            .filter(|(key, _)| !read_only(key))
+            .filter_map(|(key, v)| {
+                Some(ImportedCounter {
+                    key: key.clone(),
+                    previous: before.get(key).and_then(|v| v.as_i64()),
+                    value: v.as_i64()?,
+                })
+            })
+            .collect();
+        imported.sort_by(|a, b| a.key.cmp(&b.key));
+        imported
+    }
+
+    /// Write to `path` atomically, so a crash mid-write never leaves a
+    /// truncated backup.
+    pub fn write_file(&self, path: &Path) -> io::Result<()> {
+        let text = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+        write_atomic(path, &text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{Map, Value, json};
+
+    use super::{Backup, ImportMode, ImportedCounter};
+
+    fn globals(v: Value) -> Map<String, Value> {
+        v.as_object().unwrap().clone()
+    }
+
+    fn imported(key: &str, previous: Option<i64>, value: i64) -> ImportedCounter {
+        ImportedCounter {
+            key: key.into(),
+            previous,
+            value,
+        }
+    }
+
+    #[test]
+    fn snapshot_round_trips_through_parse() {
+        let g = globals(json!({
+            "counters": { "deaths": 3 },
+            "timers": { "ctx1": { "duration_ms": 60000 } },
+            "goals": { "deaths": { "target": 10 } },
+        }));
+        let text = serde_json::to_string(&Backup::snapshot(&g, "now".into())).unwrap();
+        let back = Backup::parse(&text).unwrap();
+        assert_eq!(back.counters["deaths"], 3);
+        assert!(back.timers.contains_key("ctx1"));
+        assert!(back.stopwatches.is_empty());
+    }
+
+    #[test]
+    fn parse_refuses_newer_versions_and_bad_counters() {
+        assert!(Backup::parse(r#"{"version": 99}"#).is_err());
+        assert!(Backup::parse(r#"{"version": 1, "counters": {"a": "x"}}"#).is_err());
+        assert!(Backup::parse("[]").is_err());
+    }
+
+    #[test]
+    fn merge_keeps_other_counters_and_reports_every_imported_one() {
+        let mut g = globals(json!({ "counters": { "a": 1, "b": 2, "d": 4 } }));
+        let backup =
+            Backup::parse(r#"{"version": 1, "counters": {"b": 5, "c": 7, "d": 4}}"#).unwrap();
+        let imports = backup.restore(&mut g, ImportMode::Merge, |_| false);
+        assert_eq!(g["counters"], json!({ "a": 1, "b": 5, "c": 7, "d": 4 }));
+        assert_eq!(
+            imports,
+            vec![
+                imported("b", Some(2), 5),
+                imported("c", None, 7),
+                imported("d", Some(4), 4),
+            ]
+        );
+    }
+
+    #[test]
+    fn replace_resets_missing_counters_to_their_initial_and_drops_clocks() {
+        let mut g = globals(json!({
+            "counters": { "a": 1, "b": 2, "c": 9 },
+            "initials": { "c": 3 },
+            "stopwatches": { "ctx9": { "elapsed_ms": 5 } },
+        }));
+        let backup = Backup::parse(r#"{"version": 1, "counters": {"b": 2}}"#).unwrap();
+        let imports = backup.restore(&mut g, ImportMode::Replace, |_| false);
+        assert_eq!(g["counters"], json!({ "a": 0, "b": 2, "c": 3 }));
+        assert_eq!(g["stopwatches"], json!({}));
+        assert_eq!(
+            imports,
+            vec![
+                imported("a", Some(1), 0),
+                imported("b", Some(2), 2),
+                imported("c", Some(9), 3),
+            ]
+        );
+    }
+}
@@ -0,0 +1,131 @@
+//! Optional min/max limits for a counter, shared by every key bound to the
+//! same counter ID. Stored in the store's `["bounds"][counter_key]` so a press
+//! on any of those keys enforces the same limits.
+
+use serde::{Deserialize, Serialize};
+
+/// What happens when an operation would leave the `[min, max]` range.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BoundPolicy {
+    /// Stop at the bound.
+    #[default]
+    Clamp,
+    /// Wrap around to the opposite bound (round/lap counters like `1..=4`).
+    /// Needs both bounds; with only one set it behaves like `Clamp`.
+    Wrap,
+    /// Refuse the operation entirely — the value stays where it was.
+    Reject,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Bounds {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<i64>,
+    #[serde(default)]
+    pub policy: BoundPolicy,
+}
+
+impl Bounds {
+    /// Build from the PI fields. A reversed range is swapped rather than
+    /// rejected, so `max < min` never locks a counter.
+    pub fn new(min: Option<i64>, max: Option<i64>, policy: BoundPolicy) -> Self {
+        let (min, max) = match (min, max) {
+            (Some(a), Some(b)) if a > b => (Some(b), Some(a)),
+            other => other,
+        };
+        Self { min, max, policy }
+    }
+
+    /// True when neither limit is set (the policy alone means nothing).
+    pub fn is_unbounded(&self) -> bool {
+        self.min.is_none() && self.max.is_none()
+    }
+
+    /// How far `value` sits between the bounds, `0.0..=1.0` — for a level
+    /// bar. `None` unless both bounds are set and differ.
+    pub fn fraction(&self, value: i64) -> Option<f32> {
+        let (min, max) = (self.min?, self.max?);
+        let span = max as f64 - min as f64;
+        (span > 0.0).then(|| ((value as f64 - min as f64) / span).clamp(0.0, 1.0) as f32)
+    }
+
+    /// Bring `next` inside the range according to the policy. Returns `None`
+    /// when the policy is `Reject` and `next` is out of range.
+    pub fn enforce(&self, next: i64) -> Option<i64> {
+        let below = self.min.is_some_and(|m| next < m);
+        let above = self.max.is_some_and(|m| next > m);
+        if !below && !above {
+            return Some(next);
+        }
+        match (self.policy, self.min, self.max) {
+            (BoundPolicy::Reject, _, _) => None,
+            (BoundPolicy::Wrap, Some(min), Some(max)) => {
+                // i128 so the span of a full i64 range can't overflow.
+                let span = max as i128 - min as i128 + 1;
+                let offset = (next as i128 - min as i128).rem_euclid(span);
+                Some((min as i128 + offset) as i64)
+            }
+            _ => Some(if below {
+                self.min.unwrap_or(next)
+            } else {
+                self.max.unwrap_or(next)
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BoundPolicy, Bounds};
+
+    #[test]
+    fn fraction_needs_both_bounds() {
+        let b = Bounds::new(Some(0), Some(10), BoundPolicy::Clamp);
+        assert_eq!(b.fraction(5), Some(0.5));
+        assert_eq!(b.fraction(20), Some(1.0));
+        let open = Bounds::new(Some(0), None, BoundPolicy::Clamp);
+        assert_eq!(open.fraction(5), None);
+        let flat = Bounds::new(Some(3), Some(3), BoundPolicy::Clamp);
+        assert_eq!(flat.fraction(3), None);
+    }
+
+    #[test]
+    fn clamp_stops_at_either_edge() {
+        let b = Bounds::new(Some(0), Some(10), BoundPolicy::Clamp);
+        assert_eq!(b.enforce(-3), Some(0));
+        assert_eq!(b.enforce(5), Some(5));
+        assert_eq!(b.enforce(12), Some(10));
+    }
+
+    #[test]
+    fn wrap_cycles_through_the_range() {
+        let b = Bounds::new(Some(1), Some(4), BoundPolicy::Wrap);
+        assert_eq!(b.enforce(5), Some(1)); // 4 + 1
+        assert_eq!(b.enforce(0), Some(4)); // 1 - 1
+        assert_eq!(b.enforce(11), Some(3)); // several laps
+        assert_eq!(b.enforce(-7), Some(1));
+    }
+
+    #[test]
+    fn wrap_with_one_bound_clamps() {
+        let b = Bounds::new(Some(0), None, BoundPolicy::Wrap);
+        assert_eq!(b.enforce(-1), Some(0));
+        assert_eq!(b.enforce(i64::MAX), Some(i64::MAX));
+    }
+
+    #[test]
+    fn reject_refuses_out_of_range() {
+        let b = Bounds::new(None, Some(3), BoundPolicy::Reject);
+        assert_eq!(b.enforce(3), Some(3));
+        assert_eq!(b.enforce(4), None);
+    }
+
+    #[test]
+    fn reversed_range_is_swapped() {
+        let b = Bounds::new(Some(9), Some(2), BoundPolicy::Clamp);
+        assert_eq!((b.min, b.max), (Some(2), Some(9)));
+    }
+}
@@ -0,0 +1,122 @@
+//! Opt-in change log for a counter. The format is stored under the key that
+//! turned it on, in the store's `["changelog"][ctx_id]`, and covers every
+//! change to the counter whichever key made it; `ChangeLogAdapter` does the
+//! file writing.
+//!
+//! This module only turns one change into a line of text, so the formats can
+//! be tested without touching the filesystem.
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// One JSON object per line.
+    Jsonl,
+    /// Comma-separated, with a header row at the top of each file.
+    Csv,
+}
+
+impl LogFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            LogFormat::Jsonl => "jsonl",
+            LogFormat::Csv => "csv",
+        }
+    }
+
+    /// First line of a fresh log file, if the format has one.
+    pub fn header(self) -> Option<&'static str> {
+        match self {
+            LogFormat::Jsonl => None,
+            LogFormat::Csv => Some("ts,key,op,old,new,ctx"),
+        }
+    }
+}
+
+/// One counter mutation, as written to the log.
+#[derive(Debug)]
+pub struct LogRecord<'a> {
+    /// RFC 3339 local time, millisecond precision.
+    pub ts: &'a str,
+    pub key: &'a str,
+    pub op: &'a str,
+    /// `None` for a counter that didn't exist before (`null` / empty).
+    pub old: Option<i64>,
+    pub new: i64,
+    /// Context of the key that made the change (empty if none).
+    pub ctx: &'a str,
+}
+
+impl LogRecord<'_> {
+    /// Render as a single line (without the trailing newline).
+    pub fn line(&self, format: LogFormat) -> String {
+        match format {
+            LogFormat::Jsonl => json!({
+                "ts": self.ts,
+                "key": self.key,
+                "op": self.op,
+                "old": self.old,
+                "new": self.new,
+                "ctx": self.ctx,
+            })
+            .to_string(),
+            LogFormat::Csv => format!(
+                "{},{},{},{},{},{}",
+                csv_field(self.ts),
+                csv_field(self.key),
+                csv_field(self.op),
+                self.old.map_or(String::new(), |n| n.to_string()),
+                self.new,
+                csv_field(self.ctx),
+            ),
+        }
+    }
+}
+
+/// Quote a CSV field if it contains a separator, quote or line break.
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LogFormat, LogRecord};
+
+    fn record<'a>(key: &'a str) -> LogRecord<'a> {
+        LogRecord {
+            ts: "2025-03-01T20:15:00.250+01:00",
+            key,
+            op: "add 1",
+            old: Some(4),
+            new: 5,
+            ctx: "ABC123",
+        }
+    }
+
+    #[test]
+    fn jsonl_is_one_object_per_line() {
+        let line = record("deaths").line(LogFormat::Jsonl);
+        assert!(!line.contains('\n'));
+        let v: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(v["key"], "deaths");
+        assert_eq!(v["old"], 4);
+        assert_eq!(v["new"], 5);
+    }
+
+    #[test]
+    fn csv_matches_header_and_quotes_awkward_keys() {
+        let header = LogFormat::Csv.header().unwrap();
+        let line = record("deaths").line(LogFormat::Csv);
+        assert_eq!(header.split(',').count(), line.split(',').count());
+        assert_eq!(
+            record("a \"b\", c").line(LogFormat::Csv),
+            "2025-03-01T20:15:00.250+01:00,\"a \"\"b\"\", c\",add 1,4,5,ABC123"
+        );
+    }
+}
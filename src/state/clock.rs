@@ -0,0 +1,100 @@
+//! Saved timer and stopwatch state, one record per key in the store's
+//! `timers` / `stopwatches` sections. The adapters own the live state; these
+//! are what they write on every transition and read back after a restart,
+//! and what computed keys read by name for `remaining("…")` / `elapsed("…")`.
+//! A running clock's record is only written when it starts, so its live
+//! value is worked out from the anchor.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimerRecord {
+    /// Working duration (configured ± bumps).
+    pub duration_ms: u64,
+    /// The PI duration a reset returns to. Older saves lack it; they fall
+    /// back to `duration_ms`.
+    #[serde(default)]
+    pub configured_duration_ms: Option<u64>,
+    /// Time left as of `anchor_unix_ms` (or now, if paused).
+    #[serde(default)]
+    pub remaining_ms: Option<u64>,
+    /// Set while running: when `remaining_ms` was measured.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub anchor_unix_ms: Option<u64>,
+    /// The key's Timer Name, if it has one.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub name: String,
+}
+
+impl TimerRecord {
+    /// Time left at `now_unix_ms`.
+    pub fn remaining_at(&self, now_unix_ms: u64) -> u64 {
+        let remaining = self.remaining_ms.unwrap_or(self.duration_ms);
+        let since = self
+            .anchor_unix_ms
+            .map_or(0, |a| now_unix_ms.saturating_sub(a));
+        remaining.saturating_sub(since)
+    }
+
+    /// Counting down at `now_unix_ms`: started and not yet run out.
+    pub fn running_at(&self, now_unix_ms: u64) -> bool {
+        self.anchor_unix_ms.is_some() && self.remaining_at(now_unix_ms) > 0
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StopwatchRecord {
+    /// Time counted as of `anchor_unix_ms` (or now, if paused).
+    #[serde(default)]
+    pub elapsed_ms: u64,
+    /// Set while running: when `elapsed_ms` was measured.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub anchor_unix_ms: Option<u64>,
+    /// The key's Stopwatch Name, if it has one.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub name: String,
+}
+
+impl StopwatchRecord {
+    /// Time counted at `now_unix_ms`.
+    pub fn elapsed_at(&self, now_unix_ms: u64) -> u64 {
+        let since = self
+            .anchor_unix_ms
+            .map_or(0, |a| now_unix_ms.saturating_sub(a));
+        self.elapsed_ms.saturating_add(since)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{StopwatchRecord, TimerRecord};
+
+    #[test]
+    fn running_clocks_advance_from_their_anchor() {
+        let timer = TimerRecord {
+            duration_ms: 60_000,
+            configured_duration_ms: None,
+            remaining_ms: Some(30_000),
+            anchor_unix_ms: Some(1_000),
+            name: "round".into(),
+        };
+        assert_eq!(timer.remaining_at(11_000), 20_000);
+        assert!(timer.running_at(11_000));
+        assert_eq!(timer.remaining_at(90_000), 0);
+        assert!(!timer.running_at(90_000));
+
+        let paused = TimerRecord {
+            anchor_unix_ms: None,
+            remaining_ms: None,
+            ..timer
+        };
+        assert_eq!(paused.remaining_at(90_000), 60_000);
+
+        let watch = StopwatchRecord {
+            elapsed_ms: 5_000,
+            anchor_unix_ms: Some(1_000),
+            name: "match".into(),
+        };
+        assert_eq!(watch.elapsed_at(4_000), 8_000);
+    }
+}
@@ -0,0 +1,94 @@
+//! Optional target for a counter, drawn as progress on every key bound to it.
+//! Stored in the store's `["goals"][counter_key]` so shared counters show the
+//! same progress everywhere.
+
+use serde::{Deserialize, Serialize};
+
+/// How progress toward the goal is drawn.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GoalStyle {
+    /// A bar along the bottom of the key, under the number.
+    #[default]
+    Bar,
+    /// A ring of dots around the key, with the number in the middle.
+    Ring,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Goal {
+    pub target: i64,
+    #[serde(default)]
+    pub style: GoalStyle,
+    /// Play a chime when the value crosses into "reached".
+    #[serde(default)]
+    pub beep: bool,
+}
+
+impl Goal {
+    /// Reached once the value gets to the target, counting from zero toward
+    /// it — so a negative target is reached by going *down* to it.
+    pub fn reached(&self, value: i64) -> bool {
+        if self.target >= 0 {
+            value >= self.target
+        } else {
+            value <= self.target
+        }
+    }
+
+    /// Fraction of the way from zero to the target, in `0.0..=1.0`.
+    pub fn progress(&self, value: i64) -> f32 {
+        if self.reached(value) {
+            return 1.0;
+        }
+        if self.target == 0 {
+            return 0.0;
+        }
+        (value as f64 / self.target as f64).clamp(0.0, 1.0) as f32
+    }
+
+    /// True when a change from `previous` to `value` crosses into "reached".
+    /// Staying above the goal, or dropping back below it, is not a crossing.
+    pub fn crossed(&self, previous: i64, value: i64) -> bool {
+        !self.reached(previous) && self.reached(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Goal, GoalStyle};
+
+    fn goal(target: i64) -> Goal {
+        Goal {
+            target,
+            style: GoalStyle::Bar,
+            beep: false,
+        }
+    }
+
+    #[test]
+    fn progress_counts_from_zero() {
+        let g = goal(50);
+        assert_eq!(g.progress(0), 0.0);
+        assert_eq!(g.progress(25), 0.5);
+        assert_eq!(g.progress(-5), 0.0);
+        assert_eq!(g.progress(80), 1.0);
+    }
+
+    #[test]
+    fn negative_target_is_reached_going_down() {
+        let g = goal(-10);
+        assert!(!g.reached(-9));
+        assert!(g.reached(-10));
+        assert_eq!(g.progress(-5), 0.5);
+    }
+
+    #[test]
+    fn only_the_upward_crossing_counts() {
+        let g = goal(10);
+        assert!(g.crossed(9, 10));
+        assert!(g.crossed(3, 25));
+        assert!(!g.crossed(10, 11)); // already reached
+        assert!(!g.crossed(11, 9)); // dropping back
+    }
+}
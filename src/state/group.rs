@@ -0,0 +1,13 @@
+//! Group membership for a counter, so a "Group control" key can act on every
+//! counter sharing a tag. Stored under the key that tags the counter, in the
+//! store's `["groups"][ctx_id]`.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GroupMember {
+    pub group: String,
+    /// The counter's initial value, which a group reset returns it to.
+    #[serde(default)]
+    pub initial: i64,
+}
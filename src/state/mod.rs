@@ -1,5 +1,29 @@
+pub mod backup;
+pub mod bounds;
+pub mod changelog;
+pub mod clock;
+pub mod goal;
+pub mod group;
+pub mod schedule;
+pub mod stats;
+pub mod store;
+pub mod triggers;
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use serde_json::{Map, Value};
-use streamdeck_lib::Context;
+
+use backup::{Backup, ImportMode, ImportedCounter};
+use bounds::Bounds;
+use changelog::LogFormat;
+use clock::{StopwatchRecord, TimerRecord};
+use goal::Goal;
+use group::GroupMember;
+use schedule::ScheduleRecord;
+use stats::CounterStats;
+use store::{Store, section_mut};
+use triggers::Trigger;
 
 /// Resolve the counter key for a button.
 /// If `counter_id` is empty/whitespace, the button's own context UUID is used
@@ -12,40 +36,645 @@ pub fn counter_key(counter_id: &str, ctx_id: &str) -> String {
     }
 }
 
-/// Load the counter, initialising it to `initial` if not yet stored.
-/// Returns the current value.
-pub fn init_or_load_counter(cx: &Context, key: &str, initial: i64) -> i64 {
-    let mut out = initial;
-    cx.globals().with_mut(|m| {
-        let obj = m
-            .entry("counters".to_string())
-            .or_insert_with(|| Value::Object(Map::new()));
-        if let Some(map) = obj.as_object_mut() {
-            out = map.get(key).and_then(|v| v.as_i64()).unwrap_or(initial);
-            map.entry(key.to_string()).or_insert(Value::from(out));
-        }
-    });
-    out
+/// Load the counter, initialising it to `initial` if not yet stored (unless
+/// it is [published](is_published)). Returns the current value.
+pub fn init_or_load_counter(st: &dyn Store, key: &str, initial: i64) -> i64 {
+    if is_published(st, key) {
+        return read_counter(st, key, initial);
+    }
+    st.update(|m| {
+        let map = section_mut(m, "counters");
+        let out = map.get(key).and_then(|v| v.as_i64()).unwrap_or(initial);
+        map.entry(key.to_string()).or_insert(Value::from(out));
+        out
+    })
 }
 
 /// Read the current value of a counter (returns `initial` if not found).
-pub fn read_counter(cx: &Context, key: &str, initial: i64) -> i64 {
-    cx.globals()
-        .get("counters")
+pub fn read_counter(st: &dyn Store, key: &str, initial: i64) -> i64 {
+    st.get("counters")
         .and_then(|v| v.get(key).and_then(|v| v.as_i64()))
         .unwrap_or(initial)
 }
 
-/// Write a counter value to global settings (automatically persisted by SD).
-/// Does NOT publish the `COUNTER_CHANGED` topic — callers must do that after
-/// calling this function so they can include the value in the notification.
-pub fn write_counter(cx: &Context, key: &str, value: i64) {
-    cx.globals().with_mut(|m| {
-        let obj = m
-            .entry("counters".to_string())
-            .or_insert_with(|| Value::Object(Map::new()));
-        if let Some(map) = obj.as_object_mut() {
-            map.insert(key.to_string(), Value::from(value));
+/// Every counter's current value, keyed by counter key.
+pub fn read_counters(st: &dyn Store) -> Map<String, Value> {
+    match st.get("counters") {
+        Some(Value::Object(map)) => map,
+        _ => Map::new(),
+    }
+}
+
+/// One step of an [`apply_to_counters`] batch: the counter's value before
+/// and after it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CounterUpdate {
+    pub key: String,
+    pub previous: i64,
+    pub value: i64,
+}
+
+/// Read, compute and write a batch of counters in a single store update, so
+/// presses that overlap on a shared counter can't lose each other's change.
+///
+/// Each step is `(key, initial, op)`. `compute` gets the op, the counter's
+/// current value (`initial` if unset, or the result of an earlier step on the
+/// same key) and its bounds, and returns the new value — or `None` to reject
+/// it, which discards the whole batch. Returns one update per step, in order.
+///
+/// A batch that touches a [published](is_published) ID is rejected as a
+/// whole, like a bounds rejection: those are read-only.
+///
+/// Does NOT publish the `COUNTER_CHANGED` topic — callers do that with the
+/// returned values. Every changed value is pushed onto the counter's undo
+/// history (and the redo side cleared), so it can be stepped back with
+/// [`undo_counter`].
+pub fn apply_to_counters<T>(
+    st: &dyn Store,
+    steps: impl IntoIterator<Item = (String, i64, T)>,
+    mut compute: impl FnMut(&T, i64, &Bounds) -> Option<i64>,
+) -> Option<Vec<CounterUpdate>> {
+    let steps: Vec<_> = steps.into_iter().collect();
+    if steps.iter().any(|(key, _, _)| is_published(st, key)) {
+        return None;
+    }
+    st.update(|m| {
+        let mut updates: Vec<CounterUpdate> = Vec::new();
+        for (key, initial, op) in steps {
+            let previous = match updates.iter().rev().find(|u| u.key == key) {
+                Some(u) => u.value,
+                None => counter_in(m, &key).unwrap_or(initial),
+            };
+            let bounds = m
+                .get("bounds")
+                .and_then(|b| b.get(&key))
+                .and_then(|v| serde_json::from_value(v.clone()).ok())
+                .unwrap_or_default();
+            let value = compute(&op, previous, &bounds)?;
+            updates.push(CounterUpdate {
+                key,
+                previous,
+                value,
+            });
+        }
+        for u in &updates {
+            let old = section_mut(m, "counters")
+                .insert(u.key.clone(), Value::from(u.value))
+                .and_then(|v| v.as_i64());
+            if let Some(old) = old.filter(|&old| old != u.value) {
+                let h = history_entry(m, &u.key);
+                push_bounded(h, "undo", old);
+                h.insert("redo".to_string(), Value::Array(Vec::new()));
+            }
+        }
+        Some(updates)
+    })
+}
+
+/// [`apply_to_counters`] for a single counter.
+pub fn apply_to_counter(
+    st: &dyn Store,
+    key: &str,
+    initial: i64,
+    compute: impl FnOnce(i64, &Bounds) -> Option<i64>,
+) -> Option<CounterUpdate> {
+    let mut compute = Some(compute);
+    let steps = [(key.to_string(), initial, ())];
+    apply_to_counters(st, steps, |_, base, bounds| compute.take()?(base, bounds))?.pop()
+}
+
+fn counter_in(m: &Map<String, Value>, key: &str) -> Option<i64> {
+    m.get("counters")?.get(key)?.as_i64()
+}
+
+/// Replace the set of IDs computed keys publish their results as.
+pub fn set_published(st: &dyn Store, ids: impl IntoIterator<Item = String>) {
+    st.published().replace(ids);
+}
+
+/// Whether `key` is an ID a computed key publishes, so nothing — a press, an
+/// undo, an import — may write it.
+pub fn is_published(st: &dyn Store, key: &str) -> bool {
+    st.published().contains(key)
+}
+
+// ── History ────────────────────────────────────────────────────────────────
+
+/// How many past values each counter remembers for undo.
+const HISTORY_LIMIT: usize = 50;
+
+/// Step a counter back to its previous value. Returns `(previous, restored)`,
+/// or `None` when there is nothing to undo. Does NOT publish `COUNTER_CHANGED`.
+pub fn undo_counter(st: &dyn Store, key: &str) -> Option<(i64, i64)> {
+    step_history(st, key, "undo", "redo")
+}
+
+/// Re-apply the most recently undone value. Returns `(previous, restored)`,
+/// or `None` when there is nothing to redo. Does NOT publish `COUNTER_CHANGED`.
+pub fn redo_counter(st: &dyn Store, key: &str) -> Option<(i64, i64)> {
+    step_history(st, key, "redo", "undo")
+}
+
+/// Number of (undo, redo) steps available for a counter.
+pub fn history_depth(st: &dyn Store, key: &str) -> (usize, usize) {
+    let Some(h) = st.get("history").and_then(|v| v.get(key).cloned()) else {
+        return (0, 0);
+    };
+    let len = |side: &str| h.get(side).and_then(|v| v.as_array()).map_or(0, Vec::len);
+    (len("undo"), len("redo"))
+}
+
+/// Pop a value from the `from` stack, push the current value onto `to`, and
+/// make the popped value current — all under one `with_mut`. A
+/// [published](is_published) ID has nothing to step.
+fn step_history(st: &dyn Store, key: &str, from: &str, to: &str) -> Option<(i64, i64)> {
+    if is_published(st, key) {
+        return None;
+    }
+    st.update(|m| {
+        let current = m
+            .get("counters")
+            .and_then(|c| c.get(key))
+            .and_then(|v| v.as_i64());
+        let h = history_entry(m, key);
+        let target = h
+            .get_mut(from)
+            .and_then(|v| v.as_array_mut())
+            .and_then(|a| a.pop())
+            .and_then(|v| v.as_i64())?;
+        if let Some(current) = current {
+            push_bounded(h, to, current);
+        }
+        section_mut(m, "counters").insert(key.to_string(), Value::from(target));
+        Some((current.unwrap_or(target), target))
+    })
+}
+
+/// `store["history"][key]`, created on demand.
+fn history_entry<'a>(m: &'a mut Map<String, Value>, key: &str) -> &'a mut Map<String, Value> {
+    let entry = section_mut(m, "history")
+        .entry(key.to_string())
+        .or_insert_with(|| Value::Object(Map::new()));
+    if !entry.is_object() {
+        *entry = Value::Object(Map::new());
+    }
+    entry.as_object_mut().unwrap()
+}
+
+/// Push onto `h[side]`, dropping the oldest entries beyond [`HISTORY_LIMIT`].
+fn push_bounded(h: &mut Map<String, Value>, side: &str, value: i64) {
+    let stack = h
+        .entry(side.to_string())
+        .or_insert_with(|| Value::Array(Vec::new()));
+    if !stack.is_array() {
+        *stack = Value::Array(Vec::new());
+    }
+    let arr = stack.as_array_mut().unwrap();
+    arr.push(Value::from(value));
+    if arr.len() > HISTORY_LIMIT {
+        let excess = arr.len() - HISTORY_LIMIT;
+        arr.drain(..excess);
+    }
+}
+
+/// Snapshot counters, timers and stopwatches for export.
+pub fn export_backup(st: &dyn Store, exported_at: String) -> Backup {
+    st.update(|m| Backup::snapshot(m, exported_at))
+}
+
+/// Write an imported backup into the store in one step, leaving
+/// [published](is_published) IDs alone. Returns every counter the import
+/// wrote; the caller publishes `COUNTER_CHANGED` for them and tells the
+/// timer/stopwatch adapters to reload.
+pub fn import_backup(st: &dyn Store, backup: &Backup, mode: ImportMode) -> Vec<ImportedCounter> {
+    st.update(|m| backup.restore(m, mode, |key| is_published(st, key)))
+}
+
+/// Read the shared bounds for a counter (unbounded if none are stored).
+pub fn read_bounds(st: &dyn Store, key: &str) -> Bounds {
+    st.record("bounds", key).unwrap_or_default()
+}
+
+/// Store the bounds for a counter so every key bound to it enforces them.
+/// Unbounded values remove the entry instead of storing an empty record.
+pub fn write_bounds(st: &dyn Store, key: &str, bounds: &Bounds) {
+    let record = Some(bounds).filter(|b| !b.is_unbounded());
+    st.put_record("bounds", key, record);
+}
+
+/// Read the shared goal for a counter, if it has one.
+pub fn read_goal(st: &dyn Store, key: &str) -> Option<Goal> {
+    st.record("goals", key)
+}
+
+/// Store (or with `None`, remove) the goal for a counter.
+pub fn write_goal(st: &dyn Store, key: &str, goal: Option<&Goal>) {
+    st.put_record("goals", key, goal);
+}
+
+/// Every trigger rule the keys bound to a counter set, each once (empty if
+/// none).
+pub fn read_triggers(st: &dyn Store, key: &str) -> Vec<Trigger> {
+    let mut rules: Vec<Trigger> = Vec::new();
+    for (_, set) in key_entries::<Vec<Trigger>>(st, "triggers", key) {
+        for rule in set {
+            if !rules.contains(&rule) {
+                rules.push(rule);
+            }
+        }
+    }
+    rules
+}
+
+/// Store the trigger rules key `ctx_id` sets on counter `key`; an empty list
+/// removes the entry.
+pub fn write_triggers(st: &dyn Store, ctx_id: &str, key: &str, rules: &[Trigger]) {
+    let rules = Some(rules).filter(|r| !r.is_empty());
+    write_key_entry(st, "triggers", ctx_id, key, rules);
+}
+
+/// The initial value a reset returns a counter to, as configured on the
+/// keys bound to it — for resets from keys that don't own the counter. 0 when
+/// none of them set one.
+pub fn read_initial(st: &dyn Store, key: &str) -> i64 {
+    st.record("initials", key).unwrap_or(0)
+}
+
+/// Store a counter's initial value; 0 (the default) removes the entry.
+pub fn write_initial(st: &dyn Store, key: &str, initial: i64) {
+    st.put_record("initials", key, Some(&initial).filter(|&&i| i != 0));
+}
+
+/// Store (or with `None`, remove) the group key `ctx_id` puts counter `key`
+/// in.
+pub fn write_group(st: &dyn Store, ctx_id: &str, key: &str, member: Option<&GroupMember>) {
+    write_key_entry(st, "groups", ctx_id, key, member);
+}
+
+/// Every counter tagged with `group`, with the initial value a reset returns
+/// it to. Sorted by key so batch operations run in a stable order; a counter
+/// tagged by several keys is listed once.
+pub fn group_members(st: &dyn Store, group: &str) -> Vec<(String, i64)> {
+    let mut members: Vec<(String, i64)> = key_entries::<GroupMember>(st, "groups", "")
+        .into_iter()
+        .filter(|(_, m)| m.group == group)
+        .map(|(key, m)| (key, m.initial))
+        .collect();
+    members.sort();
+    members.dedup_by(|a, b| a.0 == b.0);
+    members
+}
+
+/// Store (or with `None`, remove) the reset schedule key `ctx_id` sets on
+/// counter `key`.
+pub fn write_schedule(st: &dyn Store, ctx_id: &str, key: &str, record: Option<&ScheduleRecord>) {
+    write_key_entry(st, "schedules", ctx_id, key, record);
+}
+
+/// Every counter with a reset schedule. If several keys set one on a
+/// counter, the first by key ID applies.
+pub fn all_schedules(st: &dyn Store) -> Vec<(String, ScheduleRecord)> {
+    let mut schedules = BTreeMap::new();
+    for (key, record) in key_entries(st, "schedules", "") {
+        schedules.entry(key).or_insert(record);
+    }
+    schedules.into_iter().collect()
+}
+
+/// When the counter was last reset by its schedule (unix ms), if ever.
+pub fn read_last_reset(st: &dyn Store, key: &str) -> Option<i64> {
+    st.record("resets", key)
+}
+
+pub fn write_last_reset(st: &dyn Store, key: &str, unix_ms: i64) {
+    st.put_record("resets", key, Some(&unix_ms));
+}
+
+/// Read the change-log format for a counter (`None` when logging is off). If
+/// several keys turn it on, the first by key ID picks the format.
+pub fn read_log_format(st: &dyn Store, key: &str) -> Option<LogFormat> {
+    let mut formats = key_entries(st, "changelog", key).into_iter();
+    formats.next().map(|(_, format)| format)
+}
+
+/// Turn logging on for counter `key` from key `ctx_id` (or off, with `None`).
+pub fn write_log_format(st: &dyn Store, ctx_id: &str, key: &str, format: Option<LogFormat>) {
+    write_key_entry(st, "changelog", ctx_id, key, format.as_ref());
+}
+
+// ── Per-key settings ─────────────────────────────────────────────────────────
+
+/// Sections whose entries belong to the key that set them rather than to a
+/// counter, so keys sharing a counter don't overwrite each other's, and a
+/// key that drops a setting or goes away removes only its own.
+const KEY_SECTIONS: [&str; 4] = ["triggers", "changelog", "groups", "schedules"];
+
+/// `[section][ctx_id]`: the counter a key's setting is for, and the setting.
+#[derive(Serialize, Deserialize)]
+struct KeyEntry<K, T> {
+    counter: K,
+    config: T,
+}
+
+/// Store (or with `None`, remove) key `ctx_id`'s entry in `section`.
+fn write_key_entry<T: Serialize + ?Sized>(
+    st: &dyn Store,
+    section: &str,
+    ctx_id: &str,
+    counter: &str,
+    config: Option<&T>,
+) {
+    let entry = config.map(|config| KeyEntry { counter, config });
+    st.put_record(section, ctx_id, entry.as_ref());
+}
+
+/// Every key's entry in `section` as `(counter, setting)`, in key ID order —
+/// only those for `counter`, unless it is empty.
+fn key_entries<T: DeserializeOwned>(
+    st: &dyn Store,
+    section: &str,
+    counter: &str,
+) -> Vec<(String, T)> {
+    let Some(Value::Object(entries)) = st.get(section) else {
+        return Vec::new();
+    };
+    entries
+        .into_iter()
+        .filter_map(|(_, v)| serde_json::from_value::<KeyEntry<String, T>>(v).ok())
+        .filter(|e| counter.is_empty() || e.counter == counter)
+        .map(|e| (e.counter, e.config))
+        .collect()
+}
+
+/// Remove every per-key setting of key `ctx_id`, once it has gone.
+pub fn forget_key(st: &dyn Store, ctx_id: &str) {
+    st.update(|m| {
+        for section in KEY_SECTIONS {
+            if let Some(Value::Object(entries)) = m.get_mut(section) {
+                entries.remove(ctx_id);
+            }
         }
     });
 }
+
+// ── Stats ────────────────────────────────────────────────────────────────────
+
+/// Read a counter's statistics, if it has changed since they were added.
+pub fn read_stats(st: &dyn Store, key: &str) -> Option<CounterStats> {
+    st.record("stats", key)
+}
+
+/// Fold a change from `previous` to `value` into a counter's statistics, in
+/// one store update. A counter without stats starts them at `previous`.
+pub fn record_stats(
+    st: &dyn Store,
+    key: &str,
+    previous: i64,
+    value: i64,
+    pressed: bool,
+    now_unix_ms: i64,
+) {
+    st.update(|m| {
+        let section = section_mut(m, "stats");
+        let mut stats = section
+            .get(key)
+            .and_then(|v| serde_json::from_value(v.clone()).ok())
+            .unwrap_or_else(|| CounterStats::new(previous));
+        stats.record(value, pressed, now_unix_ms);
+        if let Ok(v) = serde_json::to_value(stats) {
+            section.insert(key.to_string(), v);
+        }
+    });
+}
+
+/// Start a new session: every counter's session-start value becomes its
+/// current value. Returns the counters it touched.
+pub fn start_stats_session(st: &dyn Store) -> Vec<String> {
+    st.update(|m| {
+        let counters: Vec<(String, i64)> = m
+            .get("counters")
+            .and_then(|v| v.as_object())
+            .map(|c| {
+                c.iter()
+                    .filter_map(|(k, v)| Some((k.clone(), v.as_i64()?)))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let section = section_mut(m, "stats");
+        let mut started = Vec::new();
+        for (key, value) in counters {
+            let mut stats: CounterStats = section
+                .get(&key)
+                .and_then(|v| serde_json::from_value(v.clone()).ok())
+                .unwrap_or_else(|| CounterStats::new(value));
+            stats.session_start = value;
+            if let Ok(v) = serde_json::to_value(stats) {
+                section.insert(key.clone(), v);
+                started.push(key);
+            }
+        }
+        started
+    })
+}
+
+// ── Clocks ───────────────────────────────────────────────────────────────────
+
+/// The saved state of the timer on key `ctx_id`, if any.
+pub fn read_timer(st: &dyn Store, ctx_id: &str) -> Option<TimerRecord> {
+    st.record("timers", ctx_id)
+}
+
+pub fn write_timer(st: &dyn Store, ctx_id: &str, record: &TimerRecord) {
+    st.put_record("timers", ctx_id, Some(record));
+}
+
+/// The saved state of the stopwatch on key `ctx_id`, if any.
+pub fn read_stopwatch(st: &dyn Store, ctx_id: &str) -> Option<StopwatchRecord> {
+    st.record("stopwatches", ctx_id)
+}
+
+pub fn write_stopwatch(st: &dyn Store, ctx_id: &str, record: &StopwatchRecord) {
+    st.put_record("stopwatches", ctx_id, Some(record));
+}
+
+/// The saved timer named `name`. If several keys share the name, the first
+/// by key ID.
+pub fn find_timer(st: &dyn Store, name: &str) -> Option<TimerRecord> {
+    find_named(st, "timers", name, |t: &TimerRecord| &t.name)
+}
+
+/// The saved stopwatch named `name`, as for [`find_timer`].
+pub fn find_stopwatch(st: &dyn Store, name: &str) -> Option<StopwatchRecord> {
+    find_named(st, "stopwatches", name, |s: &StopwatchRecord| &s.name)
+}
+
+fn find_named<T: DeserializeOwned>(
+    st: &dyn Store,
+    section: &str,
+    name: &str,
+    name_of: impl Fn(&T) -> &str,
+) -> Option<T> {
+    if name.is_empty() {
+        return None;
+    }
+    let Value::Object(records) = st.get(section)? else {
+        return None;
+    };
+    records
+        .into_iter()
+        .filter_map(|(_, v)| serde_json::from_value(v).ok())
+        .find(|r| name_of(r) == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::backup::{Backup, ImportMode};
+    use super::clock::StopwatchRecord;
+    use super::group::GroupMember;
+    use super::store::{MemoryStore, Store};
+    use super::triggers::parse_triggers;
+    use super::{
+        apply_to_counter, apply_to_counters, find_stopwatch, forget_key, group_members,
+        history_depth, import_backup, init_or_load_counter, read_counter, read_counters,
+        read_triggers, set_published, undo_counter, write_group, write_stopwatch, write_triggers,
+    };
+
+    #[test]
+    fn concurrent_presses_on_one_counter_are_not_lost() {
+        const THREADS: usize = 8;
+        const PRESSES: usize = 500;
+        let store = MemoryStore::default();
+        let st: &dyn Store = &store;
+        thread::scope(|s| {
+            for _ in 0..THREADS {
+                s.spawn(|| {
+                    for _ in 0..PRESSES {
+                        apply_to_counter(st, "deaths", 0, |base, _| Some(base + 1));
+                    }
+                });
+            }
+        });
+        assert_eq!(read_counter(st, "deaths", 0), (THREADS * PRESSES) as i64);
+    }
+
+    #[test]
+    fn a_rejected_step_discards_the_batch() {
+        let store = MemoryStore::default();
+        let st: &dyn Store = &store;
+        let steps = [("a".to_string(), 0, 5), ("b".to_string(), 0, -1)];
+        let updates = apply_to_counters(st, steps, |&n, base, _| (n >= 0).then_some(base + n));
+        assert_eq!(updates, None);
+        assert_eq!(read_counter(st, "a", 0), 0);
+        assert_eq!(history_depth(st, "a"), (0, 0));
+    }
+
+    #[test]
+    fn published_ids_are_read_only() {
+        let store = MemoryStore::default();
+        let st: &dyn Store = &store;
+        set_published(st, ["kd".to_string()]);
+        let steps = [("a".to_string(), 0, ()), ("kd".to_string(), 0, ())];
+        let updates = apply_to_counters(st, steps, |_, base, _| Some(base + 1));
+        assert_eq!(updates, None);
+        assert_eq!(read_counter(st, "a", 0), 0);
+        assert_eq!(init_or_load_counter(st, "kd", 5), 5);
+        assert!(!read_counters(st).contains_key("kd"));
+        set_published(st, []);
+        assert!(apply_to_counter(st, "kd", 0, |base, _| Some(base + 1)).is_some());
+    }
+
+    #[test]
+    fn undo_and_import_leave_published_ids_alone() {
+        let store = MemoryStore::default();
+        let st: &dyn Store = &store;
+        apply_to_counter(st, "kd", 0, |_, _| Some(3));
+        apply_to_counter(st, "kd", 0, |_, _| Some(4));
+        set_published(st, ["kd".to_string()]);
+        assert_eq!(undo_counter(st, "kd"), None);
+        assert_eq!(read_counter(st, "kd", 0), 4);
+
+        let text = r#"{"version": 1, "counters": {"kd": 9, "a": 1}}"#;
+        let backup = Backup::parse(text).unwrap();
+        let imported = import_backup(st, &backup, ImportMode::Merge);
+        assert_eq!(
+            imported.iter().map(|c| c.key.as_str()).collect::<Vec<_>>(),
+            ["a"]
+        );
+        let imported = import_backup(
+            st,
+            &Backup::parse(r#"{"version": 1}"#).unwrap(),
+            ImportMode::Replace,
+        );
+        assert_eq!(
+            imported.iter().map(|c| c.key.as_str()).collect::<Vec<_>>(),
+            ["a"]
+        );
+        assert_eq!(read_counter(st, "kd", 0), 4);
+        assert_eq!(read_counter(st, "a", 9), 0);
+    }
+
+    #[test]
+    fn repeated_keys_chain_within_a_batch() {
+        let store = MemoryStore::default();
+        let st: &dyn Store = &store;
+        let steps = [("a".to_string(), 10, 1), ("a".to_string(), 10, 2)];
+        let updates = apply_to_counters(st, steps, |&n, base, _| Some(base + n)).unwrap();
+        let values: Vec<_> = updates.iter().map(|u| (u.previous, u.value)).collect();
+        assert_eq!(values, [(10, 11), (11, 13)]);
+        assert_eq!(read_counter(st, "a", 0), 13);
+    }
+
+    #[test]
+    fn keys_sharing_a_counter_keep_their_own_settings() {
+        let store = MemoryStore::default();
+        let st: &dyn Store = &store;
+        let member = |group: &str| GroupMember {
+            group: group.into(),
+            initial: 0,
+        };
+        write_group(st, "ctx-a", "deaths", Some(&member("score")));
+        write_group(st, "ctx-b", "deaths", Some(&member("score")));
+        write_group(st, "ctx-c", "kills", Some(&member("score")));
+        write_triggers(st, "ctx-a", "deaths", &parse_triggers(">= 10: beep"));
+        write_triggers(
+            st,
+            "ctx-b",
+            "deaths",
+            &parse_triggers(">= 10: beep\n== 0: flash"),
+        );
+        assert_eq!(
+            group_members(st, "score"),
+            [("deaths".into(), 0), ("kills".into(), 0)]
+        );
+        assert_eq!(read_triggers(st, "deaths").len(), 2);
+
+        // One key dropping its setting, or going, leaves the other's.
+        write_group(st, "ctx-a", "deaths", None);
+        assert_eq!(group_members(st, "score").len(), 2);
+        forget_key(st, "ctx-b");
+        assert_eq!(group_members(st, "score"), [("kills".into(), 0)]);
+        assert_eq!(read_triggers(st, "deaths").len(), 1);
+        forget_key(st, "ctx-a");
+        assert!(read_triggers(st, "deaths").is_empty());
+    }
+
+    #[test]
+    fn clocks_are_found_by_name() {
+        let store = MemoryStore::default();
+        let st: &dyn Store = &store;
+        let named = |name: &str, elapsed_ms| StopwatchRecord {
+            elapsed_ms,
+            anchor_unix_ms: None,
+            name: name.into(),
+        };
+        write_stopwatch(st, "ctx-b", &named("match", 2_000));
+        write_stopwatch(st, "ctx-a", &named("", 1_000));
+        write_stopwatch(st, "ctx-c", &named("match", 3_000));
+        let found = find_stopwatch(st, "match").map(|s| s.elapsed_ms);
+        assert_eq!(found, Some(2_000));
+        assert_eq!(find_stopwatch(st, "round"), None);
+        assert_eq!(find_stopwatch(st, ""), None);
+    }
+}
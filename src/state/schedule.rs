@@ -0,0 +1,169 @@
+//! Automatic reset schedules for counters (daily, weekly, monthly, or every
+//! N hours). The schedule is stored under the key that set it, in the store's
+//! `["schedules"][ctx_id]`; the time of the last reset goes separately in
+//! `["resets"][counter_key]`, so editing the schedule in the PI (or the key
+//! leaving the page) doesn't lose it. `ScheduleAdapter` does the resetting.
+//!
+//! Times are local wall-clock time. Everything here works on
+//! `NaiveDateTime`s in local time so it can be tested without a clock.
+
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "every", rename_all = "lowercase")]
+pub enum Schedule {
+    /// Every day at `at` (minutes after midnight).
+    Day { at: u32 },
+    /// Every week on `weekday` (0 = Monday) at `at`.
+    Week { weekday: u32, at: u32 },
+    /// Every month on `day` at `at`; clamped to the last day in short months.
+    Month { day: u32, at: u32 },
+    /// Every `hours` hours, counted from the last reset.
+    Hours { hours: u32 },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScheduleRecord {
+    pub schedule: Schedule,
+    /// The counter's initial value, which a scheduled reset returns it to.
+    #[serde(default)]
+    pub initial: i64,
+}
+
+impl Schedule {
+    /// The latest scheduled instant after `last` and no later than `now`, if
+    /// one has passed — i.e. whether a reset is due, and the time to record
+    /// for it. Several missed resets (plugin was off for a week) collapse into
+    /// the most recent one.
+    pub fn due_since(&self, last: NaiveDateTime, now: NaiveDateTime) -> Option<NaiveDateTime> {
+        let due = match *self {
+            Schedule::Hours { hours } => {
+                let period = Duration::hours(hours.max(1) as i64);
+                let periods = (now - last).num_seconds() / period.num_seconds();
+                last + period * periods as i32
+            }
+            _ => self.latest_at_or_before(now),
+        };
+        (due > last && due <= now).then_some(due)
+    }
+
+    /// Most recent calendar occurrence at or before `now`. Only meaningful
+    /// for the calendar kinds.
+    fn latest_at_or_before(&self, now: NaiveDateTime) -> NaiveDateTime {
+        let today = now.date();
+        match *self {
+            Schedule::Day { at } => {
+                let t = today.and_time(time_of_day(at));
+                if t > now { t - Duration::days(1) } else { t }
+            }
+            Schedule::Week { weekday, at } => {
+                let back = (today.weekday().num_days_from_monday() + 7 - weekday % 7) % 7;
+                let t = (today - Duration::days(back as i64)).and_time(time_of_day(at));
+                if t > now { t - Duration::days(7) } else { t }
+            }
+            Schedule::Month { day, at } => {
+                let t = month_day(today.year(), today.month(), day).and_time(time_of_day(at));
+                if t <= now {
+                    return t;
+                }
+                let (y, m) = if today.month() == 1 {
+                    (today.year() - 1, 12)
+                } else {
+                    (today.year(), today.month() - 1)
+                };
+                month_day(y, m, day).and_time(time_of_day(at))
+            }
+            Schedule::Hours { .. } => now,
+        }
+    }
+}
+
+/// Parse `HH:MM` into minutes after midnight.
+pub fn parse_time_of_day(s: &str) -> Option<u32> {
+    let t = NaiveTime::parse_from_str(s.trim(), "%H:%M").ok()?;
+    Some(t.hour() * 60 + t.minute())
+}
+
+fn time_of_day(minutes: u32) -> NaiveTime {
+    let m = minutes.min(24 * 60 - 1);
+    NaiveTime::from_hms_opt(m / 60, m % 60, 0).unwrap_or_default()
+}
+
+/// `day` of the given month, clamped to the month's length.
+fn month_day(year: i32, month: u32, day: u32) -> NaiveDate {
+    let day = day.clamp(1, 31);
+    (1..=day)
+        .rev()
+        .find_map(|d| NaiveDate::from_ymd_opt(year, month, d))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, NaiveDateTime};
+
+    use super::{Schedule, parse_time_of_day};
+
+    fn at(y: i32, mo: u32, d: u32, h: u32, mi: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(y, mo, d)
+            .unwrap()
+            .and_hms_opt(h, mi, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn daily_fires_once_after_the_time() {
+        let s = Schedule::Day { at: 0 };
+        let last = at(2025, 3, 1, 0, 0);
+        assert_eq!(s.due_since(last, at(2025, 3, 1, 23, 59)), None);
+        assert_eq!(
+            s.due_since(last, at(2025, 3, 2, 0, 0)),
+            Some(at(2025, 3, 2, 0, 0))
+        );
+    }
+
+    #[test]
+    fn missed_resets_collapse_into_the_latest() {
+        // Plugin was off from Monday to Thursday afternoon.
+        let s = Schedule::Day { at: 6 * 60 };
+        let due = s.due_since(at(2025, 3, 3, 7, 0), at(2025, 3, 6, 15, 0));
+        assert_eq!(due, Some(at(2025, 3, 6, 6, 0)));
+    }
+
+    #[test]
+    fn weekly_on_monday() {
+        let s = Schedule::Week { weekday: 0, at: 0 };
+        // 2025-03-09 is a Sunday, 2025-03-10 a Monday.
+        let last = at(2025, 3, 3, 0, 0);
+        assert_eq!(s.due_since(last, at(2025, 3, 9, 23, 0)), None);
+        assert_eq!(
+            s.due_since(last, at(2025, 3, 10, 9, 0)),
+            Some(at(2025, 3, 10, 0, 0))
+        );
+    }
+
+    #[test]
+    fn monthly_clamps_to_short_months() {
+        let s = Schedule::Month { day: 31, at: 0 };
+        let due = s.due_since(at(2025, 1, 31, 0, 0), at(2025, 3, 1, 12, 0));
+        assert_eq!(due, Some(at(2025, 2, 28, 0, 0)));
+    }
+
+    #[test]
+    fn hours_keep_their_phase() {
+        let s = Schedule::Hours { hours: 4 };
+        let last = at(2025, 3, 1, 10, 0);
+        assert_eq!(s.due_since(last, at(2025, 3, 1, 13, 59)), None);
+        assert_eq!(
+            s.due_since(last, at(2025, 3, 1, 22, 30)),
+            Some(at(2025, 3, 1, 22, 0))
+        );
+    }
+
+    #[test]
+    fn parses_time_of_day() {
+        assert_eq!(parse_time_of_day("06:30"), Some(390));
+        assert_eq!(parse_time_of_day("24:00"), None);
+    }
+}
@@ -0,0 +1,123 @@
+//! Running statistics for a counter: how often it was pressed, the highest
+//! and lowest values it reached, when it last changed, and where it stood
+//! when the plugin started. Stored in the store's `["stats"][counter_key]`
+//! next to the value, and kept up to date by `StatsAdapter`.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CounterStats {
+    /// Changes made from a key: presses, dial turns, undo/redo.
+    #[serde(default)]
+    pub presses: u64,
+    pub high: i64,
+    pub low: i64,
+    /// Unix ms of the last change.
+    #[serde(default)]
+    pub changed_unix_ms: Option<i64>,
+    /// The value when the plugin last started (or when the counter was
+    /// first seen, if that was later).
+    pub session_start: i64,
+}
+
+impl CounterStats {
+    /// Stats for a counter first seen at `value`.
+    pub fn new(value: i64) -> Self {
+        Self {
+            presses: 0,
+            high: value,
+            low: value,
+            changed_unix_ms: None,
+            session_start: value,
+        }
+    }
+
+    /// Fold in one change to `value`.
+    pub fn record(&mut self, value: i64, pressed: bool, now_unix_ms: i64) {
+        if pressed {
+            self.presses = self.presses.saturating_add(1);
+        }
+        self.high = self.high.max(value);
+        self.low = self.low.min(value);
+        self.changed_unix_ms = Some(now_unix_ms);
+    }
+
+    /// One statistic as a number, given the counter's current `value`.
+    /// [`Stat::Changed`] is unix seconds, or 0 if it never changed.
+    pub fn get(&self, stat: Stat, value: i64) -> i64 {
+        match stat {
+            Stat::Presses => i64::try_from(self.presses).unwrap_or(i64::MAX),
+            Stat::High => self.high,
+            Stat::Low => self.low,
+            Stat::Changed => self.changed_unix_ms.map_or(0, |ms| ms.div_euclid(1000)),
+            Stat::Start => self.session_start,
+            Stat::Session => value.saturating_sub(self.session_start),
+        }
+    }
+}
+
+/// A statistic a Counter Stats key shows, or an expression reads as
+/// `counter.stat`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Stat {
+    #[default]
+    Presses,
+    High,
+    Low,
+    Changed,
+    /// The value at session start.
+    Start,
+    /// The change since session start.
+    Session,
+}
+
+impl Stat {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "presses" => Some(Stat::Presses),
+            "high" => Some(Stat::High),
+            "low" => Some(Stat::Low),
+            "changed" => Some(Stat::Changed),
+            "start" => Some(Stat::Start),
+            "session" => Some(Stat::Session),
+            _ => None,
+        }
+    }
+
+    /// Short label drawn under the value.
+    pub fn label(self) -> &'static str {
+        match self {
+            Stat::Presses => "presses",
+            Stat::High => "high",
+            Stat::Low => "low",
+            Stat::Changed => "changed",
+            Stat::Start => "start",
+            Stat::Session => "session",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CounterStats, Stat};
+
+    #[test]
+    fn record_tracks_extremes_and_presses() {
+        let mut s = CounterStats::new(5);
+        s.record(9, true, 1_000);
+        s.record(-2, true, 2_500);
+        s.record(3, false, 4_000);
+        assert_eq!(s.presses, 2);
+        assert_eq!((s.high, s.low), (9, -2));
+        assert_eq!(s.get(Stat::Changed, 3), 4);
+        assert_eq!(s.get(Stat::Start, 3), 5);
+        assert_eq!(s.get(Stat::Session, 3), -2);
+    }
+
+    #[test]
+    fn stat_names_parse_case_insensitively() {
+        assert_eq!(Stat::parse(" High "), Some(Stat::High));
+        assert_eq!(Stat::parse("session"), Some(Stat::Session));
+        assert_eq!(Stat::parse("peak"), None);
+    }
+}
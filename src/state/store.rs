@@ -0,0 +1,436 @@
+//! Where counters, clocks and their per-counter config are kept. Everything
+//! in `state` reads and writes through the [`Store`] trait, so the backing
+//! can change without touching callers:
+//!
+//! - [`GlobalsStore`] — Stream Deck's global settings (the default). Simple,
+//!   but every write round-trips the whole map through `setGlobalSettings`.
+//! - [`FileStore`] — a JSON file in the plugin's data directory, written
+//!   atomically and at most every [`WRITE_DELAY`]. Keeps global settings
+//!   small however many counters there are.
+//! - [`MemoryStore`] — in-process only, for tests.
+//!
+//! `Context` is itself a `Store` that forwards to whichever backend is
+//! active, so `state` functions take `&dyn Store` and callers pass `cx`. The
+//! active backend is recorded in global settings under `storage`, which stays
+//! there whatever the backend.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{
+    Arc, Mutex, OnceLock, RwLock,
+    atomic::{AtomicBool, Ordering},
+};
+use std::time::Duration;
+
+use crossbeam_channel::Sender;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::{Map, Value};
+use streamdeck_lib::Context;
+use tracing::warn;
+
+use crate::paths::{data_dir, write_atomic};
+
+/// Top-level sections the plugin keeps in its store — moved as a whole when
+/// switching backends.
+pub const SECTIONS: &[&str] = &[
+    "counters",
+    "history",
+    "timers",
+    "stopwatches",
+    "bounds",
+    "goals",
+    "triggers",
+    "changelog",
+    "groups",
+    "schedules",
+    "resets",
+    "initials",
+    "stats",
+];
+
+/// A JSON document of named sections (`counters`, `timers`, …).
+pub trait Store: Send + Sync {
+    /// A copy of one top-level section, if present.
+    fn get(&self, section: &str) -> Option<Value>;
+
+    /// Run `f` over the whole document and save the result. Nothing else
+    /// reads or writes the store in between, so a read-modify-write inside
+    /// `f` is atomic.
+    fn with_mut(&self, f: &mut dyn FnMut(&mut Map<String, Value>));
+
+    /// The IDs no counter may write. The plugin's backends share one set,
+    /// so it outlives a switch between them.
+    fn published(&self) -> &Published {
+        &PUBLISHED
+    }
+}
+
+/// IDs computed keys publish their results as, which are read-only to
+/// everything else. Kept in memory beside the document rather than in it:
+/// `ComputedAdapter` rebuilds it from the keys it has.
+#[derive(Default)]
+pub struct Published(Mutex<BTreeSet<String>>);
+
+static PUBLISHED: Published = Published(Mutex::new(BTreeSet::new()));
+
+impl Published {
+    pub fn replace(&self, ids: impl IntoIterator<Item = String>) {
+        *self.0.lock().unwrap() = ids.into_iter().collect();
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.0.lock().unwrap().contains(key)
+    }
+}
+
+impl dyn Store + '_ {
+    /// [`Store::with_mut`] for a closure that returns a value.
+    pub fn update<R>(&self, f: impl FnOnce(&mut Map<String, Value>) -> R) -> R {
+        let mut f = Some(f);
+        let mut out = None;
+        self.with_mut(&mut |m| out = f.take().map(|f| f(m)));
+        out.expect("with_mut runs the closure")
+    }
+
+    /// Read a typed record at `[section][key]`.
+    pub fn record<T: DeserializeOwned>(&self, section: &str, key: &str) -> Option<T> {
+        self.get(section)
+            .and_then(|v| v.get(key).cloned())
+            .and_then(|v| serde_json::from_value(v).ok())
+    }
+
+    /// Store a typed record at `[section][key]`; `None` removes it.
+    pub fn put_record<T: Serialize + ?Sized>(&self, section: &str, key: &str, record: Option<&T>) {
+        let value = record.and_then(|r| serde_json::to_value(r).ok());
+        self.update(|m| {
+            let map = section_mut(m, section);
+            match value {
+                Some(v) => map.insert(key.to_string(), v),
+                None => map.remove(key),
+            };
+        });
+    }
+}
+
+/// `m[section]` as an object, created (or replaced, if it isn't one) on demand.
+pub fn section_mut<'a>(m: &'a mut Map<String, Value>, section: &str) -> &'a mut Map<String, Value> {
+    let v = m
+        .entry(section.to_string())
+        .or_insert_with(|| Value::Object(Map::new()));
+    if !v.is_object() {
+        *v = Value::Object(Map::new());
+    }
+    v.as_object_mut().unwrap()
+}
+
+// ── Backends ─────────────────────────────────────────────────────────────────
+
+/// Stream Deck's global settings, persisted by Stream Deck itself.
+pub struct GlobalsStore<'a>(pub &'a Context);
+
+impl Store for GlobalsStore<'_> {
+    fn get(&self, section: &str) -> Option<Value> {
+        self.0.globals().get(section)
+    }
+
+    fn with_mut(&self, f: &mut dyn FnMut(&mut Map<String, Value>)) {
+        self.0.globals().with_mut(|m| f(m));
+    }
+}
+
+/// How long [`FileStore`] waits after a change before writing the file. Every
+/// change in that time (an auto-repeat runs at up to 30 a second) goes out in
+/// the one write.
+pub const WRITE_DELAY: Duration = Duration::from_millis(250);
+
+/// A JSON file, held in memory and rewritten in the background a short
+/// [`WRITE_DELAY`] after a change. Writes go to a temp file beside it and are
+/// renamed over it, so a crash mid-write leaves the previous version intact;
+/// a crash before the write loses only the changes of that last moment.
+pub struct FileStore {
+    path: PathBuf,
+    doc: Arc<Mutex<Map<String, Value>>>,
+    /// Set (under the `doc` lock) by a change the file doesn't have yet.
+    dirty: Arc<AtomicBool>,
+    /// Wakes the writer thread after a change.
+    wake: Sender<()>,
+}
+
+impl FileStore {
+    /// Open (or start) the store at `path`. A missing file is an empty
+    /// store; an unreadable one is logged and also starts empty, rather than
+    /// keeping the plugin from running.
+    pub fn open(path: PathBuf) -> Self {
+        let doc = match fs::read_to_string(&path) {
+            Ok(text) => serde_json::from_str(&text).unwrap_or_else(|e| {
+                warn!("storage: ignoring unreadable {}: {e}", path.display());
+                Map::new()
+            }),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Map::new(),
+            Err(e) => {
+                warn!("storage: could not read {}: {e}", path.display());
+                Map::new()
+            }
+        };
+        let doc = Arc::new(Mutex::new(doc));
+        let dirty = Arc::new(AtomicBool::new(false));
+        let (wake, rx) = crossbeam_channel::unbounded();
+        let (path2, doc2, dirty2) = (path.clone(), Arc::clone(&doc), Arc::clone(&dirty));
+        std::thread::spawn(move || {
+            // After each change, wait for more, then write them all at once.
+            // Exits when the store is dropped.
+            while rx.recv().is_ok() {
+                std::thread::sleep(WRITE_DELAY);
+                while rx.try_recv().is_ok() {}
+                save_if_dirty(&path2, &doc2.lock().unwrap(), &dirty2);
+            }
+        });
+        Self {
+            path,
+            doc,
+            dirty,
+            wake,
+        }
+    }
+
+    /// Write any pending change now rather than waiting for the writer
+    /// thread, e.g. on the way out.
+    pub fn flush(&self) {
+        save_if_dirty(&self.path, &self.doc.lock().unwrap(), &self.dirty);
+    }
+}
+
+/// Write `doc` to `path` if it changed since the last write. The caller holds
+/// the document lock, so two writes never race on the temp file.
+fn save_if_dirty(path: &Path, doc: &Map<String, Value>, dirty: &AtomicBool) {
+    if !dirty.swap(false, Ordering::Relaxed) {
+        return;
+    }
+    let result = serde_json::to_string(doc)
+        .map_err(io::Error::other)
+        .and_then(|text| write_atomic(path, &text));
+    if let Err(e) = result {
+        warn!("storage: could not write {}: {e}", path.display());
+    }
+}
+
+impl Store for FileStore {
+    fn get(&self, section: &str) -> Option<Value> {
+        self.doc.lock().unwrap().get(section).cloned()
+    }
+
+    fn with_mut(&self, f: &mut dyn FnMut(&mut Map<String, Value>)) {
+        let mut doc = self.doc.lock().unwrap();
+        // Many updates only read (or write back what was there); those
+        // shouldn't cost a rewrite of the whole file.
+        let before = doc.clone();
+        f(&mut doc);
+        if *doc == before {
+            return;
+        }
+        self.dirty.store(true, Ordering::Relaxed);
+        drop(doc);
+        let _ = self.wake.send(());
+    }
+}
+
+/// Nothing leaves the process — for tests. Each has its own published IDs.
+#[cfg(test)]
+#[derive(Default)]
+pub struct MemoryStore(Mutex<Map<String, Value>>, Published);
+
+#[cfg(test)]
+impl Store for MemoryStore {
+    fn get(&self, section: &str) -> Option<Value> {
+        self.0.lock().unwrap().get(section).cloned()
+    }
+
+    fn with_mut(&self, f: &mut dyn FnMut(&mut Map<String, Value>)) {
+        f(&mut self.0.lock().unwrap());
+    }
+
+    fn published(&self) -> &Published {
+        &self.1
+    }
+}
+
+// ── Backend selection ────────────────────────────────────────────────────────
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    #[default]
+    Globals,
+    File,
+}
+
+/// Which backend currently holds the plugin's state.
+pub fn active_backend(cx: &Context) -> Backend {
+    cx.globals()
+        .get("storage")
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+static FILE_STORE: OnceLock<Option<Arc<FileStore>>> = OnceLock::new();
+
+/// The file store at `<data dir>/state.json`, opened on first use. `None`
+/// when the OS reports no data directory.
+fn file_store() -> Option<Arc<FileStore>> {
+    FILE_STORE
+        .get_or_init(|| data_dir().map(|d| Arc::new(FileStore::open(d.join("state.json")))))
+        .clone()
+}
+
+/// Held for reading by every access through `Context`, and for writing while
+/// [`switch_backend`] moves the state, so no access can land on the old
+/// backend between the copy and the switch.
+static SWITCHING: RwLock<()> = RwLock::new(());
+
+impl Store for Context {
+    fn get(&self, section: &str) -> Option<Value> {
+        let _switch = SWITCHING.read().unwrap();
+        match active_file_store(self) {
+            Some(file) => file.get(section),
+            None => GlobalsStore(self).get(section),
+        }
+    }
+
+    fn with_mut(&self, f: &mut dyn FnMut(&mut Map<String, Value>)) {
+        let _switch = SWITCHING.read().unwrap();
+        match active_file_store(self) {
+            Some(file) => file.with_mut(f),
+            None => GlobalsStore(self).with_mut(f),
+        }
+    }
+}
+
+/// Write out any change the file store is still holding back. Called once the
+/// plugin stops, so quitting Stream Deck right after a press doesn't lose it.
+pub fn flush() {
+    if let Some(Some(file)) = FILE_STORE.get() {
+        file.flush();
+    }
+}
+
+fn active_file_store(cx: &Context) -> Option<Arc<FileStore>> {
+    match active_backend(cx) {
+        Backend::File => file_store(),
+        Backend::Globals => None,
+    }
+}
+
+/// Move the plugin's state to `backend` and make it the active one. Reads and
+/// writes through `Context` wait until the move is done.
+pub fn switch_backend(cx: &Context, backend: Backend) -> Result<(), String> {
+    let _switch = SWITCHING.write().unwrap();
+    let current = active_backend(cx);
+    if current == backend {
+        return Ok(());
+    }
+    let file = file_store().ok_or("no data directory for the state file")?;
+    let globals = GlobalsStore(cx);
+    match backend {
+        Backend::File => migrate(&globals, &*file),
+        Backend::Globals => migrate(&*file, &globals),
+    }
+    cx.globals().with_mut(|m| {
+        m.insert(
+            "storage".to_string(),
+            serde_json::to_value(backend).unwrap(),
+        );
+    });
+    Ok(())
+}
+
+/// Copy every [`SECTIONS`] entry from `from` to `to` (replacing what `to`
+/// had), then remove them from `from`. Anything else in either is untouched.
+pub fn migrate(from: &dyn Store, to: &dyn Store) {
+    let moved = from.update(|m| {
+        SECTIONS
+            .iter()
+            .filter_map(|&s| Some((s.to_string(), m.remove(s)?)))
+            .collect::<Vec<_>>()
+    });
+    to.update(|m| {
+        for s in SECTIONS {
+            m.remove(*s);
+        }
+        m.extend(moved);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{Value, json};
+
+    use super::{FileStore, MemoryStore, Store, migrate};
+
+    #[test]
+    fn records_round_trip_and_remove() {
+        let store = MemoryStore::default();
+        let st: &dyn Store = &store;
+        st.put_record("goals", "deaths", Some(&json!({ "target": 10 })));
+        assert_eq!(
+            st.record::<Value>("goals", "deaths"),
+            Some(json!({ "target": 10 }))
+        );
+        st.put_record::<Value>("goals", "deaths", None);
+        assert_eq!(st.record::<Value>("goals", "deaths"), None);
+        assert_eq!(st.get("goals"), Some(json!({})));
+    }
+
+    #[test]
+    fn file_store_survives_a_reopen() {
+        let dir = std::env::temp_dir().join(format!("counter-store-{}", std::process::id()));
+        let path = dir.join("state.json");
+        let _ = std::fs::remove_file(&path);
+
+        let file = FileStore::open(path.clone());
+        let st: &dyn Store = &file;
+        st.put_record("counters", "deaths", Some(&3));
+        file.flush();
+        assert!(path.exists());
+        assert!(!path.with_extension("json.tmp").exists());
+
+        let reopened = FileStore::open(path.clone());
+        assert_eq!(
+            (&reopened as &dyn Store).record::<i64>("counters", "deaths"),
+            Some(3)
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn file_store_skips_writes_that_change_nothing() {
+        let dir = std::env::temp_dir().join(format!("counter-noop-{}", std::process::id()));
+        let path = dir.join("state.json");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let file = FileStore::open(path.clone());
+        let st: &dyn Store = &file;
+        st.update(|m| m.get("counters").cloned());
+        st.update(|m| m.remove("goals"));
+        file.flush();
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn migrate_moves_only_state_sections() {
+        let from = MemoryStore::default();
+        let to = MemoryStore::default();
+        from.with_mut(&mut |m| {
+            m.insert("counters".into(), json!({ "a": 1 }));
+            m.insert("storage".into(), json!("globals"));
+        });
+        to.with_mut(&mut |m| {
+            m.insert("counters".into(), json!({ "stale": 9 }));
+        });
+        migrate(&from, &to);
+        assert_eq!(to.get("counters"), Some(json!({ "a": 1 })));
+        assert_eq!(from.get("counters"), None);
+        assert_eq!(from.get("storage"), Some(json!("globals")));
+    }
+}
@@ -0,0 +1,193 @@
+//! Threshold triggers — rules like "when ≥ 100, chime" attached to a counter.
+//! Stored under the key that set them, in the store's `["triggers"][ctx_id]`;
+//! every key bound to the counter sees them all, and `TriggerAdapter` fires
+//! them once per crossing.
+//!
+//! Rules are written one per line in the PI:
+//!
+//! ```text
+//! >= 100: beep, bg #2e7d32
+//! == 0: flash, publish out-of-lives
+//! ```
+//!
+//! Effects: `beep`, `flash`, `bg #RRGGBB` (key background while the rule
+//! holds), and `publish NAME` (a `COUNTER_TRIGGERED` event that presses
+//! every Counter key whose On Event is `NAME`).
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Cmp {
+    Ge,
+    Gt,
+    Le,
+    Lt,
+    Eq,
+    Ne,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Trigger {
+    pub cmp: Cmp,
+    pub threshold: i64,
+    #[serde(default)]
+    pub beep: bool,
+    #[serde(default)]
+    pub flash: bool,
+    /// Key background (RGB) while the condition holds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bg: Option<[u8; 3]>,
+    /// Event name published on `COUNTER_TRIGGERED` when the rule fires.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub publish: Option<String>,
+}
+
+impl Trigger {
+    /// Whether the condition holds for `value`.
+    pub fn matches(&self, value: i64) -> bool {
+        let t = self.threshold;
+        match self.cmp {
+            Cmp::Ge => value >= t,
+            Cmp::Gt => value > t,
+            Cmp::Le => value <= t,
+            Cmp::Lt => value < t,
+            Cmp::Eq => value == t,
+            Cmp::Ne => value != t,
+        }
+    }
+
+    /// True when a change from `previous` to `value` makes the condition
+    /// start holding. Further changes while it keeps holding don't re-fire.
+    pub fn crossed(&self, previous: i64, value: i64) -> bool {
+        !self.matches(previous) && self.matches(value)
+    }
+}
+
+/// Background colour for `value`: the last matching rule with a `bg` wins.
+pub fn background(rules: &[Trigger], value: i64) -> Option<[u8; 3]> {
+    rules
+        .iter()
+        .rev()
+        .filter(|r| r.matches(value))
+        .find_map(|r| r.bg)
+}
+
+/// Parse the PI's rule list, one rule per line. Blank lines are skipped, as
+/// are malformed ones (logged), so one typo doesn't disable the rest.
+pub fn parse_triggers(text: &str) -> Vec<Trigger> {
+    text.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .filter_map(|line| match parse_line(line) {
+            Ok(t) => Some(t),
+            Err(e) => {
+                warn!("ignoring trigger {line:?}: {e}");
+                None
+            }
+        })
+        .collect()
+}
+
+fn parse_line(line: &str) -> Result<Trigger, String> {
+    let (cond, effects) = line
+        .split_once(':')
+        .ok_or("expected `<condition>: <effects>`")?;
+
+    let cond = cond.trim();
+    let (cmp, rest) = [
+        (">=", Cmp::Ge),
+        ("<=", Cmp::Le),
+        ("==", Cmp::Eq),
+        ("!=", Cmp::Ne),
+        (">", Cmp::Gt),
+        ("<", Cmp::Lt),
+        ("=", Cmp::Eq),
+    ]
+    .into_iter()
+    .find_map(|(tok, cmp)| cond.strip_prefix(tok).map(|rest| (cmp, rest)))
+    .ok_or("condition must start with >=, >, <=, <, == or !=")?;
+    let threshold = rest
+        .trim()
+        .parse::<i64>()
+        .map_err(|_| format!("`{}` is not a whole number", rest.trim()))?;
+
+    let mut t = Trigger {
+        cmp,
+        threshold,
+        beep: false,
+        flash: false,
+        bg: None,
+        publish: None,
+    };
+    for effect in effects.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let (name, arg) = match effect.split_once(char::is_whitespace) {
+            Some((n, a)) => (n, a.trim()),
+            None => (effect, ""),
+        };
+        match name.to_ascii_lowercase().as_str() {
+            "beep" | "sound" => t.beep = true,
+            "flash" => t.flash = true,
+            "bg" | "color" | "colour" => t.bg = Some(parse_hex_color(arg)?),
+            "publish" if !arg.is_empty() => t.publish = Some(arg.to_string()),
+            "publish" => return Err("`publish` needs an event name".into()),
+            other => return Err(format!("unknown effect `{other}`")),
+        }
+    }
+    Ok(t)
+}
+
+/// `#RRGGBB` (the `#` is optional).
+fn parse_hex_color(s: &str) -> Result<[u8; 3], String> {
+    let hex = s.trim_start_matches('#');
+    let bad = || format!("`{s}` is not a #RRGGBB colour");
+    if hex.len() != 6 || !hex.is_ascii() {
+        return Err(bad());
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| bad());
+    Ok([channel(0)?, channel(2)?, channel(4)?])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Cmp, background, parse_triggers};
+
+    #[test]
+    fn parses_rules_and_effects() {
+        let rules =
+            parse_triggers(">= 100: beep, bg #2E7D32\n\n== 0: flash, publish out-of-lives\n");
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].cmp, Cmp::Ge);
+        assert_eq!(rules[0].threshold, 100);
+        assert!(rules[0].beep && !rules[0].flash);
+        assert_eq!(rules[0].bg, Some([0x2e, 0x7d, 0x32]));
+        assert_eq!(rules[1].cmp, Cmp::Eq);
+        assert!(rules[1].flash);
+        assert_eq!(rules[1].publish.as_deref(), Some("out-of-lives"));
+    }
+
+    #[test]
+    fn malformed_lines_are_skipped() {
+        let rules = parse_triggers("100: beep\n> x: beep\n< 5: explode\n< 5: bg red\n<= -3: beep");
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rules[0].threshold, -3);
+    }
+
+    #[test]
+    fn fires_once_per_crossing() {
+        let r = &parse_triggers(">= 100: beep")[0];
+        assert!(r.crossed(99, 100));
+        assert!(!r.crossed(100, 150)); // still above
+        assert!(!r.crossed(150, 20)); // dropping out doesn't fire
+        assert!(r.crossed(20, 120)); // crossing again does
+    }
+
+    #[test]
+    fn last_matching_background_wins() {
+        let rules = parse_triggers(">= 10: bg #000001\n>= 20: bg #000002\n>= 30: beep");
+        assert_eq!(background(&rules, 5), None);
+        assert_eq!(background(&rules, 15), Some([0, 0, 1]));
+        assert_eq!(background(&rules, 35), Some([0, 0, 2]));
+    }
+}
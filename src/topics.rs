@@ -1,16 +1,94 @@
 use streamdeck_lib::TopicId;
 
+use std::fmt;
+
+use crate::expr::{Arith, Expr, Number};
+
 // ── Counter ────────────────────────────────────────────────────────────────
 
-/// Published whenever a counter value changes.
-/// Subscribed by `CounterAction` (for shared counter displays)
-/// and `ComputedAction` (to recalculate expressions).
+/// Published whenever a counter value changes, including a computed key's
+/// published virtual counter (by `ComputedAdapter`, with an empty `source`).
+/// Subscribed by `CounterAction` (for shared counter displays),
+/// `ComputedAdapter` (to recalculate expressions), `GoalAdapter` /
+/// `TriggerAdapter` (to react when a goal or threshold is crossed),
+/// `ChangeLogAdapter` (to record it), and `StatsAdapter` (to update the
+/// counter's statistics).
 pub const COUNTER_CHANGED: TopicId<CounterChanged> = TopicId::new("counter_changed");
 
 #[derive(Clone, Debug)]
 pub struct CounterChanged {
     pub counter_key: String,
+    /// The value before this change — lets subscribers detect crossings.
+    /// `None` when there was none: a counter new from an import, or a
+    /// published value's first result.
+    pub previous: Option<i64>,
     pub value: i64,
+    /// What made the change, e.g. `add 1`, `reset` or `undo` (for the log).
+    pub op: String,
+    /// What sort of change it was, for subscribers that treat them apart.
+    pub kind: ChangeKind,
+    /// Context of the key that made the change; empty when no key did.
+    pub source: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChangeKind {
+    /// Counted up or down: Add and Subtract.
+    Step,
+    /// Jumped to a value of the op's choosing: Set, Multiply, Random,
+    /// Toggle and the rest.
+    Set,
+    /// Back to the initial value, from a key or a schedule.
+    Reset,
+    /// Written by a backup import.
+    Import,
+    /// Stepped back or forward by Undo / Redo.
+    History,
+    /// A computed key's published value following what it reads.
+    Computed,
+}
+
+/// Published by `TriggerAdapter` each time a counter's threshold rule fires.
+/// `CounterAction` flashes the counter's keys when `flash` is set, and
+/// presses every key whose On Event is `event` (the rule's `publish NAME`,
+/// empty if none).
+pub const COUNTER_TRIGGERED: TopicId<CounterTriggered> = TopicId::new("counter_triggered");
+
+#[derive(Clone, Debug)]
+pub struct CounterTriggered {
+    pub counter_key: String,
+    pub event: String,
+    pub flash: bool,
+}
+
+/// Published by `StatsAdapter` after it updates a counter's statistics, so
+/// `StatsAction` and `ComputedAdapter` can re-read them.
+pub const STATS_CHANGED: TopicId<StatsChanged> = TopicId::new("stats_changed");
+
+#[derive(Clone, Debug)]
+pub struct StatsChanged {
+    pub counter_key: String,
+}
+
+/// Control channel from RateAction → RateAdapter. The adapter records every
+/// `COUNTER_CHANGED` whether or not a rate key is on screen, so a key shows a
+/// full window's rate as soon as it mounts.
+pub const RATE_CTL: TopicId<RateControl> = TopicId::new("rate_ctl");
+
+#[derive(Clone, Debug)]
+pub enum RateControl {
+    /// Show `counter_key`'s rate on `ctx_id`: the net change over the last
+    /// `window_secs`, scaled to "per `per_secs`". Sent on mount and on every
+    /// settings change; replaces any previous watch for the key.
+    Watch {
+        ctx_id: String,
+        counter_key: String,
+        window_secs: u64,
+        per_secs: u64,
+    },
+    /// Stop rendering `ctx_id`: the key went away, or no longer names a
+    /// counter. The counter's history is kept for other keys.
+    Unwatch { ctx_id: String },
 }
 
 // ── Timer ──────────────────────────────────────────────────────────────────
@@ -23,12 +101,14 @@ pub const TIMER_CTL: TopicId<TimerControl> = TopicId::new("timer_ctl");
 #[derive(Clone, Debug)]
 pub enum TimerControl {
     /// Action mounted (init + first settings). Adapter creates state if absent
-    /// or rehydrates from globals; then renders current value.
+    /// or rehydrates from the store; then renders current value.
     /// `name` is the shared timer name used to route `Adjust` (may be empty).
+    /// `encoder` is set for a dial, which also gets touch-strip feedback.
     Hello {
         ctx_id: String,
         name: String,
         duration_ms: u64,
+        encoder: bool,
     },
     /// Settings changed in PI. Adapter resets to new duration only if it changed.
     Reconfigure {
@@ -43,6 +123,13 @@ pub enum TimerControl {
     /// Bump button: add/subtract from the working duration of every *idle*
     /// timer whose name matches `target`. Running timers ignore it.
     Adjust { target: String, delta_ms: i64 },
+    /// Dial rotation: step the working duration of this timer while idle,
+    /// in coarser steps the faster the dial turns. Running timers ignore it.
+    Turn { ctx_id: String, ticks: i32 },
+    /// `timers` in the store was replaced by an import: re-read every known
+    /// timer from the store and re-render it. Timers the import removed go
+    /// back to their configured duration.
+    Reload,
 }
 
 // ── Stopwatch ──────────────────────────────────────────────────────────────
@@ -51,7 +138,100 @@ pub const STOPWATCH_CTL: TopicId<StopwatchControl> = TopicId::new("stopwatch_ctl
 
 #[derive(Clone, Debug)]
 pub enum StopwatchControl {
-    Hello { ctx_id: String },
+    /// Action mounted. `name` is the Stopwatch Name expressions refer to it
+    /// by (may be empty).
+    Hello { ctx_id: String, name: String },
+    /// Settings changed in PI: pick up a renamed stopwatch.
+    Rename { ctx_id: String, name: String },
     Toggle { ctx_id: String },
     Reset { ctx_id: String },
+    /// Same as `TimerControl::Reload`; removed stopwatches go back to zero.
+    Reload,
+}
+
+// ── Clocks ─────────────────────────────────────────────────────────────────
+
+/// Published by `TimerAdapter` and `StopwatchAdapter` when a named clock
+/// starts, stops, resets or is renamed (`tick: false`), and each time a
+/// running one's displayed second changes (`tick: true`). The new state is
+/// already in the store. Subscribed by `ComputedAdapter` for
+/// `remaining("…")`, `elapsed("…")` and `running("…")`.
+pub const CLOCK_CHANGED: TopicId<ClockChanged> = TopicId::new("clock_changed");
+
+#[derive(Clone, Debug)]
+pub struct ClockChanged {
+    pub name: String,
+    pub tick: bool,
+}
+
+// ── Computed ───────────────────────────────────────────────────────────────
+
+/// Control channel from ComputedAction → ComputedAdapter. The adapter owns
+/// the graph of computed keys and evaluates them; the action parses its
+/// expression and renders the results it is sent.
+pub const COMPUTED_CTL: TopicId<ComputedControl> = TopicId::new("computed_ctl");
+
+#[derive(Clone, Debug)]
+pub enum ComputedControl {
+    /// Sent on every settings change. `None` while the key has no
+    /// expression that parses; the adapter drops it from the graph.
+    Define {
+        ctx_id: String,
+        def: Option<ComputedDef>,
+    },
+}
+
+#[derive(Clone, Debug)]
+pub struct ComputedDef {
+    pub expr: Expr,
+    pub arith: Arith,
+    pub missing_as_zero: bool,
+    /// Virtual counter ID the result is published as, if any.
+    pub publish_as: Option<String>,
+}
+
+/// Published by `ComputedAdapter` with a key's newly evaluated result, in
+/// dependency order, so a key reading another's published value renders
+/// once, after it. `Err` is why the key can't be evaluated.
+pub const COMPUTED_VALUE: TopicId<ComputedValue> = TopicId::new("computed_value");
+
+#[derive(Clone, Debug)]
+pub struct ComputedValue {
+    pub ctx_id: String,
+    pub result: Result<Number, GraphError>,
+}
+
+/// Why `ComputedAdapter` won't evaluate a key.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GraphError {
+    /// The key is on a cycle through these published IDs.
+    Cycle(Vec<String>),
+    /// The key reads, directly or through other keys, this published ID,
+    /// which is on a cycle.
+    ReadsCycle(String),
+    /// Another key defined later publishes the same ID.
+    Taken(String),
+}
+
+impl GraphError {
+    /// Short label for the key face.
+    pub fn label(&self) -> &'static str {
+        match self {
+            GraphError::Cycle(_) | GraphError::ReadsCycle(_) => "cycle",
+            GraphError::Taken(_) => "taken",
+        }
+    }
+}
+
+impl fmt::Display for GraphError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GraphError::Cycle(ids) => {
+                let ids: Vec<String> = ids.iter().map(|id| format!("'{id}'")).collect();
+                write!(f, "cycle through {}", ids.join(", "))
+            }
+            GraphError::ReadsCycle(id) => write!(f, "reads '{id}', which is on a cycle"),
+            GraphError::Taken(id) => write!(f, "'{id}' is published by another key"),
+        }
+    }
 }